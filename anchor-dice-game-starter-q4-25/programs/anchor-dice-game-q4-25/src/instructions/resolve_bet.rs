@@ -81,7 +81,8 @@ impl<'info> ResolveBet<'info> {
 
     pub fn verify_ed25519_signature(&self, sig: &[u8]) -> Result<()> {
         let cur_index = load_current_index_checked(&self.instruction_sysvar.to_account_info())? as usize;
-        let ix = load_instruction_at_checked(cur_index-1, &self.instruction_sysvar.to_account_info())?;
+        let ed25519_index = cur_index.checked_sub(1).ok_or(DiceError::Ed25519Program)?;
+        let ix = load_instruction_at_checked(ed25519_index, &self.instruction_sysvar.to_account_info())?;
 
         require_keys_eq!(ix.program_id, ed25519_program::ID, DiceError::Ed25519Program);
 
@@ -90,7 +91,7 @@ impl<'info> ResolveBet<'info> {
         let signatures = Ed25519InstructionSignatures::unpack(ix.data.as_slice()).unwrap().0;
         
         require_eq!(signatures.len(), 1, DiceError::Ed25519DataLength);
-        let signature = signatures.get(0).ok_or(DiceError::Ed25519Signature)?;
+        let signature = signatures.first().ok_or(DiceError::Ed25519Signature)?;
 
         require!(&signature.is_verifiable, DiceError::Ed25519Signature);
 
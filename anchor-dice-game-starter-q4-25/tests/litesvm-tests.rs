@@ -0,0 +1,410 @@
+use anchor_dice_game_q4_25::{accounts, instruction, Bet, DiceError};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use litesvm::LiteSVM;
+use solana_sdk::{
+    clock::Clock,
+    compute_budget::ComputeBudgetInstruction,
+    ed25519_instruction::{
+        new_ed25519_instruction_with_signature, offsets_to_ed25519_instruction,
+        Ed25519SignatureOffsets, PUBKEY_SERIALIZED_SIZE, SIGNATURE_OFFSETS_SERIALIZED_SIZE,
+        SIGNATURE_OFFSETS_START, SIGNATURE_SERIALIZED_SIZE,
+    },
+    hash::hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+const HOUSE_EDGE_BPS: u128 = 150;
+const BET_AMOUNT: u64 = LAMPORTS_PER_SOL;
+const BET_ROLL: u8 = 50;
+
+struct TestEnv {
+    svm: LiteSVM,
+    program_id: Pubkey,
+    house: Keypair,
+    player: Keypair,
+    vault: Pubkey,
+}
+
+fn setup() -> TestEnv {
+    // ============================================================================
+    // Test Env Setup: Initialize environment and deploy dice program
+    // ============================================================================
+    let mut svm = LiteSVM::new();
+
+    let program_keypair =
+        read_keypair_file("target/deploy/anchor_dice_game_q4_25-keypair.json").unwrap();
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/anchor_dice_game_q4_25.so");
+
+    svm.add_program(program_id, program_bytes).unwrap();
+
+    let house = Keypair::new();
+    let player = Keypair::new();
+    svm.airdrop(&house.pubkey(), 100 * LAMPORTS_PER_SOL)
+        .unwrap();
+    svm.airdrop(&player.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .unwrap();
+
+    let (vault, _) =
+        Pubkey::find_program_address(&[b"vault", house.pubkey().as_ref()], &program_id);
+
+    // Fund the house vault so it can cover payouts
+    let initialize_instruction = Instruction {
+        program_id,
+        accounts: accounts::Initialize {
+            house: house.pubkey(),
+            vault,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {
+            amount: 50 * LAMPORTS_PER_SOL,
+        }
+        .data(),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[initialize_instruction],
+        Some(&house.pubkey()),
+        &[&house],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    TestEnv {
+        svm,
+        program_id,
+        house,
+        player,
+        vault,
+    }
+}
+
+fn bet_address(env: &TestEnv, seed: u128) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bet", env.vault.as_ref(), &seed.to_le_bytes()],
+        &env.program_id,
+    )
+}
+
+/// Mirrors the roll derivation in `ResolveBet::resolve_bet`.
+fn roll_from_signature(sig: &[u8]) -> u8 {
+    let hash = hash(sig).to_bytes();
+
+    let lower = u128::from_le_bytes(hash[0..16].try_into().unwrap());
+    let upper = u128::from_le_bytes(hash[16..32].try_into().unwrap());
+
+    lower.wrapping_add(upper).wrapping_rem(100) as u8 + 1
+}
+
+/// Mirrors the payout calculation in `ResolveBet::resolve_bet`.
+fn expected_payout(amount: u64, roll: u8) -> u64 {
+    let bps = 10_000u128;
+    ((amount as u128) * (bps - HOUSE_EDGE_BPS) / roll as u128 / bps) as u64
+}
+
+/// Finds a bet seed whose house signature produces the requested outcome, so the
+/// tests do not depend on a lucky roll.
+fn find_seed(env: &TestEnv, player_wins: bool) -> u128 {
+    let slot = env.svm.get_sysvar::<Clock>().slot;
+
+    (0u128..)
+        .find(|seed| {
+            let (_, bump) = bet_address(env, *seed);
+            let bet = Bet {
+                player: env.player.pubkey(),
+                seed: *seed,
+                slot,
+                amount: BET_AMOUNT,
+                roll: BET_ROLL,
+                bump,
+            };
+            let sig = env.house.sign_message(&bet.to_slice());
+            (BET_ROLL > roll_from_signature(sig.as_ref())) == player_wins
+        })
+        .unwrap()
+}
+
+fn place_bet(env: &mut TestEnv, seed: u128) -> Pubkey {
+    let (bet, _) = bet_address(env, seed);
+
+    let place_bet_instruction = Instruction {
+        program_id: env.program_id,
+        accounts: accounts::PlaceBet {
+            player: env.player.pubkey(),
+            house: env.house.pubkey(),
+            vault: env.vault,
+            bet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::PlaceBet {
+            seed,
+            roll: BET_ROLL,
+            amount: BET_AMOUNT,
+        }
+        .data(),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[place_bet_instruction],
+        Some(&env.player.pubkey()),
+        &[&env.player],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+
+    bet
+}
+
+fn fetch_bet(env: &TestEnv, bet: &Pubkey) -> Bet {
+    let account = env.svm.get_account(bet).unwrap();
+    anchor_lang::AccountDeserialize::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn resolve_bet_instruction(env: &TestEnv, bet: Pubkey, sig: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: env.program_id,
+        accounts: accounts::ResolveBet {
+            player: env.player.pubkey(),
+            house: env.house.pubkey(),
+            vault: env.vault,
+            bet,
+            instruction_sysvar: sysvar::instructions::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ResolveBet { sig }.data(),
+    }
+}
+
+/// Signs `message` with `signer` and returns the signature together with the
+/// matching Ed25519 precompile instruction.
+fn sign_ed25519(signer: &Keypair, message: &[u8]) -> (Vec<u8>, Instruction) {
+    let sig = signer.sign_message(message);
+    let ix = new_ed25519_instruction_with_signature(
+        message,
+        &sig.as_ref().try_into().unwrap(),
+        &signer.pubkey().to_bytes(),
+    );
+    (sig.as_ref().to_vec(), ix)
+}
+
+/// Builds a single Ed25519 precompile instruction carrying several signatures.
+fn multi_sig_ed25519_instruction(entries: &[(&Keypair, &[u8])]) -> Instruction {
+    let mut offsets = Vec::with_capacity(entries.len());
+    let mut payload = Vec::new();
+    let data_start = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE * entries.len();
+
+    for (signer, message) in entries {
+        let public_key_offset = data_start + payload.len();
+        let signature_offset = public_key_offset + PUBKEY_SERIALIZED_SIZE;
+        let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE;
+
+        payload.extend_from_slice(&signer.pubkey().to_bytes());
+        payload.extend_from_slice(signer.sign_message(message).as_ref());
+        payload.extend_from_slice(message);
+
+        offsets.push(Ed25519SignatureOffsets {
+            signature_offset: signature_offset as u16,
+            signature_instruction_index: u16::MAX,
+            public_key_offset: public_key_offset as u16,
+            public_key_instruction_index: u16::MAX,
+            message_data_offset: message_data_offset as u16,
+            message_data_size: message.len() as u16,
+            message_instruction_index: u16::MAX,
+        });
+    }
+
+    let mut ix = offsets_to_ed25519_instruction(&offsets);
+    ix.data.extend_from_slice(&payload);
+    ix
+}
+
+fn send_as_house(env: &mut TestEnv, instructions: &[Instruction]) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&env.house.pubkey()),
+        &[&env.house],
+        env.svm.latest_blockhash(),
+    );
+    env.svm
+        .send_transaction(tx)
+        .map(|_| ())
+        .map_err(|failed| failed.err)
+}
+
+fn assert_dice_error(
+    result: Result<(), TransactionError>,
+    instruction_index: u8,
+    expected: DiceError,
+) {
+    let err = result.expect_err("transaction should have failed");
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            instruction_index,
+            InstructionError::Custom(expected.into())
+        )
+    );
+}
+
+#[test]
+fn test_resolve_bet_winning_roll_pays_out() {
+    let mut env = setup();
+
+    let seed = find_seed(&env, true);
+    let bet = place_bet(&mut env, seed);
+    let bet_state = fetch_bet(&env, &bet);
+    let bet_rent = env.svm.get_balance(&bet).unwrap();
+
+    let (sig, ed25519_ix) = sign_ed25519(&env.house, &bet_state.to_slice());
+    let roll = roll_from_signature(&sig);
+    assert!(
+        BET_ROLL > roll,
+        "Seed {} should produce a winning roll",
+        seed
+    );
+
+    let player_before = env.svm.get_balance(&env.player.pubkey()).unwrap();
+    let vault_before = env.svm.get_balance(&env.vault).unwrap();
+
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+    send_as_house(&mut env, &[ed25519_ix, resolve_ix]).unwrap();
+
+    let payout = expected_payout(BET_AMOUNT, BET_ROLL);
+    let player_after = env.svm.get_balance(&env.player.pubkey()).unwrap();
+    let vault_after = env.svm.get_balance(&env.vault).unwrap();
+
+    assert_eq!(player_after, player_before + payout + bet_rent);
+    assert_eq!(vault_after, vault_before - payout);
+    assert!(env.svm.get_account(&bet).is_none_or(|a| a.lamports == 0));
+
+    println!("Roll {} won a payout of {} lamports", roll, payout);
+}
+
+#[test]
+fn test_resolve_bet_losing_roll_keeps_stake() {
+    let mut env = setup();
+
+    let seed = find_seed(&env, false);
+    let bet = place_bet(&mut env, seed);
+    let bet_state = fetch_bet(&env, &bet);
+    let bet_rent = env.svm.get_balance(&bet).unwrap();
+
+    let (sig, ed25519_ix) = sign_ed25519(&env.house, &bet_state.to_slice());
+    let roll = roll_from_signature(&sig);
+    assert!(
+        BET_ROLL <= roll,
+        "Seed {} should produce a losing roll",
+        seed
+    );
+
+    let player_before = env.svm.get_balance(&env.player.pubkey()).unwrap();
+    let vault_before = env.svm.get_balance(&env.vault).unwrap();
+
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+    send_as_house(&mut env, &[ed25519_ix, resolve_ix]).unwrap();
+
+    let player_after = env.svm.get_balance(&env.player.pubkey()).unwrap();
+    let vault_after = env.svm.get_balance(&env.vault).unwrap();
+
+    // Only the bet account rent is returned to the player
+    assert_eq!(player_after, player_before + bet_rent);
+    assert_eq!(vault_after, vault_before);
+    assert!(env.svm.get_account(&bet).is_none_or(|a| a.lamports == 0));
+
+    println!("Roll {} lost, house kept {} lamports", roll, BET_AMOUNT);
+}
+
+#[test]
+fn test_resolve_bet_wrong_pubkey() {
+    let mut env = setup();
+
+    let bet = place_bet(&mut env, 1);
+    let bet_state = fetch_bet(&env, &bet);
+
+    // Valid signature over the right message, but not from the house
+    let impostor = Keypair::new();
+    let (sig, ed25519_ix) = sign_ed25519(&impostor, &bet_state.to_slice());
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+
+    let result = send_as_house(&mut env, &[ed25519_ix, resolve_ix]);
+    assert_dice_error(result, 1, DiceError::Ed25519Pubkey);
+}
+
+#[test]
+fn test_resolve_bet_wrong_message() {
+    let mut env = setup();
+
+    let bet = place_bet(&mut env, 1);
+    let mut bet_state = fetch_bet(&env, &bet);
+
+    // House signs a bet with a different roll than the one on chain
+    bet_state.roll = BET_ROLL + 1;
+    let (sig, ed25519_ix) = sign_ed25519(&env.house, &bet_state.to_slice());
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+
+    let result = send_as_house(&mut env, &[ed25519_ix, resolve_ix]);
+    assert_dice_error(result, 1, DiceError::Ed25519Message);
+}
+
+#[test]
+fn test_resolve_bet_extra_accounts() {
+    let mut env = setup();
+
+    let bet = place_bet(&mut env, 1);
+    let bet_state = fetch_bet(&env, &bet);
+
+    let (sig, mut ed25519_ix) = sign_ed25519(&env.house, &bet_state.to_slice());
+    ed25519_ix
+        .accounts
+        .push(AccountMeta::new_readonly(env.player.pubkey(), false));
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+
+    let result = send_as_house(&mut env, &[ed25519_ix, resolve_ix]);
+    assert_dice_error(result, 1, DiceError::Ed25519Accounts);
+}
+
+#[test]
+fn test_resolve_bet_missing_precompile() {
+    let mut env = setup();
+
+    let bet = place_bet(&mut env, 1);
+    let bet_state = fetch_bet(&env, &bet);
+
+    let (sig, _) = sign_ed25519(&env.house, &bet_state.to_slice());
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig.clone());
+
+    // No instruction at all before resolve_bet
+    let result = send_as_house(&mut env, &[resolve_ix]);
+    assert_dice_error(result, 0, DiceError::Ed25519Program);
+
+    // Preceding instruction is not the Ed25519 precompile
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
+    let result = send_as_house(&mut env, &[compute_budget_ix, resolve_ix]);
+    assert_dice_error(result, 1, DiceError::Ed25519Program);
+}
+
+#[test]
+fn test_resolve_bet_multiple_signatures() {
+    let mut env = setup();
+
+    let bet = place_bet(&mut env, 1);
+    let bet_state = fetch_bet(&env, &bet);
+    let message = bet_state.to_slice();
+
+    let sig = env.house.sign_message(&message).as_ref().to_vec();
+    let ed25519_ix =
+        multi_sig_ed25519_instruction(&[(&env.house, &message), (&env.house, &message)]);
+    let resolve_ix = resolve_bet_instruction(&env, bet, sig);
+
+    let result = send_as_house(&mut env, &[ed25519_ix, resolve_ix]);
+    assert_dice_error(result, 1, DiceError::Ed25519DataLength);
+}
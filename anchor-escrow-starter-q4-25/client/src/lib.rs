@@ -0,0 +1,207 @@
+//! Rust client for the escrow program: PDA derivation, instruction builders and
+//! account queries.
+//!
+//! Instructions moving Token-2022 transfer-hook mints need the hook's extra accounts
+//! appended to `Instruction::accounts`, see the program README.
+
+use anchor_escrow_q4_25::{accounts, instruction, Escrow, Order, OrderBook};
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, AccountDeserialize,
+    Discriminator, InstructionData, ToAccountMetas,
+};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    client_error::Error as ClientError,
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+pub use anchor_escrow_q4_25::{MakeOptions, ID as PROGRAM_ID};
+
+/// Offset of `Escrow::maker`, after the discriminator and the seed.
+const ESCROW_MAKER_OFFSET: usize = 8 + 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+    #[error("failed to decode account: {0}")]
+    Decode(#[from] anchor_lang::error::Error),
+}
+
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+/// Escrow-owned ATA holding the deposit of mint_a.
+pub fn vault_address(escrow: &Pubkey, mint_a: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(escrow, mint_a, token_program)
+}
+
+pub fn order_book_address(mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"book", mint_a.as_ref(), mint_b.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn make_ix(
+    maker: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    token_program: &Pubkey,
+    args: instruction::Make,
+) -> Instruction {
+    let escrow = escrow_address(maker, args.seed);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Make {
+            maker: *maker,
+            mint_a: *mint_a,
+            mint_b: *mint_b,
+            maker_ata_a: get_associated_token_address_with_program_id(maker, mint_a, token_program),
+            escrow,
+            order_book: order_book_address(mint_a, mint_b),
+            vault: vault_address(&escrow, mint_a, token_program),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: *token_program,
+        }
+        .to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Takes `args.amount` of the open `escrow`, paying in its current mint_b.
+pub fn take_ix(
+    taker: &Pubkey,
+    escrow: &Escrow,
+    token_program: &Pubkey,
+    args: instruction::Take,
+) -> Instruction {
+    let escrow_key = escrow_address(&escrow.maker, escrow.seed);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Take {
+            taker: *taker,
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            maker_ata_b: get_associated_token_address_with_program_id(
+                &escrow.maker,
+                &escrow.mint_b,
+                token_program,
+            ),
+            taker_ata_a: get_associated_token_address_with_program_id(
+                taker,
+                &escrow.mint_a,
+                token_program,
+            ),
+            taker_ata_b: get_associated_token_address_with_program_id(
+                taker,
+                &escrow.mint_b,
+                token_program,
+            ),
+            escrow: escrow_key,
+            order_book: order_book_address(&escrow.mint_a, &escrow.mint_b),
+            vault: vault_address(&escrow_key, &escrow.mint_a, token_program),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: *token_program,
+        }
+        .to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+pub fn refund_ix(escrow: &Escrow, token_program: &Pubkey) -> Instruction {
+    let escrow_key = escrow_address(&escrow.maker, escrow.seed);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Refund {
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            maker_ata_a: get_associated_token_address_with_program_id(
+                &escrow.maker,
+                &escrow.mint_a,
+                token_program,
+            ),
+            escrow: escrow_key,
+            order_book: order_book_address(&escrow.mint_a, &escrow.mint_b),
+            vault: vault_address(&escrow_key, &escrow.mint_a, token_program),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: *token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::Refund {}.data(),
+    }
+}
+
+/// Decodes raw `Escrow` account data, checking the discriminator.
+pub fn decode_escrow(data: &[u8]) -> Result<Escrow> {
+    Ok(Escrow::try_deserialize(&mut &data[..])?)
+}
+
+pub fn fetch_escrow(client: &RpcClient, address: &Pubkey) -> Result<Escrow> {
+    decode_escrow(&client.get_account_data(address)?)
+}
+
+/// All open escrows of `maker`, found with a `getProgramAccounts` scan filtered on
+/// the account discriminator and the maker field.
+pub fn list_escrows_by_maker(client: &RpcClient, maker: &Pubkey) -> Result<Vec<(Pubkey, Escrow)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, Escrow::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                ESCROW_MAKER_OFFSET,
+                maker.as_ref(),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    client
+        .get_program_accounts_with_config(&PROGRAM_ID, config)?
+        .into_iter()
+        .map(|(address, account)| Ok((address, decode_escrow(&account.data)?)))
+        .collect()
+}
+
+/// Up to `limit` open offers selling mint_a for mint_b, cheapest first. Pairs nobody
+/// has listed on yet have no order book and return no offers.
+pub fn best_offers(
+    client: &RpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    limit: usize,
+) -> Result<Vec<Order>> {
+    let address = order_book_address(mint_a, mint_b);
+    let Some(account) = client
+        .get_account_with_commitment(&address, client.commitment())?
+        .value
+    else {
+        return Ok(vec![]);
+    };
+
+    let order_book = OrderBook::try_deserialize(&mut account.data.as_slice())?;
+    Ok(order_book.best_offers(limit))
+}
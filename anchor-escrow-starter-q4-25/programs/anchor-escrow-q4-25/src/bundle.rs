@@ -0,0 +1,63 @@
+//! Helpers for bundle escrows, whose per-leg accounts are passed through
+//! `remaining_accounts` as consecutive `[mint, from, to]` triples.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{errors::EscrowError, state::BundleLeg};
+
+pub const ACCOUNTS_PER_LEG: usize = 3;
+
+/// Splits `accounts` into one `[mint, from, to]` triple per leg, starting at leg
+/// `first_leg`.
+pub fn leg_accounts<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    first_leg: usize,
+    legs: usize,
+) -> Result<std::slice::Chunks<'a, AccountInfo<'info>>> {
+    let start = first_leg * ACCOUNTS_PER_LEG;
+    let end = start + legs * ACCOUNTS_PER_LEG;
+    let accounts = accounts
+        .get(start..end)
+        .ok_or(EscrowError::InvalidBundleAccount)?;
+
+    Ok(accounts.chunks(ACCOUNTS_PER_LEG))
+}
+
+/// Checks that `mint` is the leg's mint and owned by `token_program`, and returns its
+/// decimals.
+pub fn load_mint(mint: &AccountInfo, leg: &BundleLeg, token_program: &Pubkey) -> Result<u8> {
+    require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccount);
+    require_keys_eq!(*mint.owner, *token_program, EscrowError::InvalidBundleAccount);
+
+    let mint = Mint::try_deserialize(&mut &mint.data.borrow()[..])?;
+
+    Ok(mint.decimals)
+}
+
+/// Checks that `token_account` is the associated token account of `authority` for the
+/// leg's mint.
+pub fn check_ata(
+    token_account: &AccountInfo,
+    authority: &Pubkey,
+    leg: &BundleLeg,
+    token_program: &Pubkey,
+) -> Result<()> {
+    require_keys_eq!(
+        token_account.key(),
+        get_associated_token_address_with_program_id(authority, &leg.mint, token_program),
+        EscrowError::InvalidBundleAccount
+    );
+
+    Ok(())
+}
+
+/// Current balance of an initialized token account.
+pub fn balance(token_account: &AccountInfo) -> Result<u64> {
+    let token_account = TokenAccount::try_deserialize(&mut &token_account.data.borrow()[..])?;
+
+    Ok(token_account.amount)
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Escrow does not allow partial fills")]
+    PartialFillDisabled,
+    #[msg("Fill exceeds the remaining amount")]
+    FillExceedsRemaining,
+    #[msg("Fill is too small to release any tokens")]
+    FillTooSmall,
+    #[msg("Overflow")]
+    Overflow,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Escrow has expired")]
+    EscrowExpired,
+    #[msg("Escrow has not expired yet")]
+    EscrowNotExpired,
+    #[msg("Set either an allowed taker or a takers root, not both")]
+    InvalidAllowlist,
+    #[msg("Taker is not allowed to take this escrow")]
+    TakerNotAllowed,
+    #[msg("Bundle must have 1 to 4 distinct mints on each side")]
+    InvalidBundle,
+    #[msg("Remaining accounts do not match the bundle")]
+    InvalidBundleAccount,
+    #[msg("Escrow has already been partially taken")]
+    TakeInProgress,
+    #[msg("Invalid Asset")]
+    InvalidAsset,
+    #[msg("Invalid Collection")]
+    InvalidCollection,
+    #[msg("Payment accounts do not match the escrow")]
+    InvalidPayment,
+    #[msg("Order book does not match the escrow mints")]
+    InvalidOrderBook,
+    #[msg("Maker cannot take their own escrow")]
+    MakerIsTaker,
+}
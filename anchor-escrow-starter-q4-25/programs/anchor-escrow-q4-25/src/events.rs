@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct EscrowUpdated {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_b: Pubkey,
+    pub remaining_deposit: u64,
+    pub receive: u64,
+}
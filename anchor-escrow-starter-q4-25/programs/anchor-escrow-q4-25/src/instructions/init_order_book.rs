@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::state::OrderBook;
+
+/// Creates the order book of a mint pair ahead of the first `make`, e.g. so an
+/// escrow can be moved to a new mint_b with `update_escrow`. Anyone can pay for it.
+#[derive(Accounts)]
+pub struct InitOrderBook<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"book", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        space = OrderBook::DISCRIMINATOR.len() + OrderBook::INIT_SPACE,
+        bump,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitOrderBook<'info> {
+    pub fn init_order_book(&mut self, bump: u8) -> Result<()> {
+        self.order_book.set_inner(OrderBook {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            orders: Vec::new(),
+            bump,
+        });
+
+        Ok(())
+    }
+}
@@ -1,10 +1,25 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::Escrow;
+use crate::{
+    errors::EscrowError,
+    state::{Escrow, OrderBook},
+    transfer::transfer_checked,
+};
+
+/// Optional terms of an escrow. `MakeOptions::default()` is a plain all-or-nothing
+/// swap open to any taker that never expires.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct MakeOptions {
+    pub partial_fill: bool,
+    pub receive_net: bool,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+    pub allowed_takers_root: Option<[u8; 32]>,
+}
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -34,6 +49,14 @@ pub struct Make<'info> {
         bump,
     )]
     pub escrow: Account<'info, Escrow>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [b"book", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        space = OrderBook::DISCRIMINATOR.len() + OrderBook::INIT_SPACE,
+        bump,
+    )]
+    pub order_book: Account<'info, OrderBook>,
     #[account(
         init,
         payer = maker,
@@ -48,20 +71,65 @@ pub struct Make<'info> {
 }
 
 impl<'info> Make<'info> {
-    pub fn init_escrow(&mut self, seed: u64, receive: u64, bump: u8) -> Result<()> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        options: MakeOptions,
+        bump: u8,
+    ) -> Result<()> {
+        require!(receive > 0, EscrowError::InvalidAmount);
+        if let Some(expires_at) = options.expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+        require!(
+            options.allowed_taker.is_none() || options.allowed_takers_root.is_none(),
+            EscrowError::InvalidAllowlist
+        );
+
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
             receive,
+            partial_fill: options.partial_fill,
+            receive_net: options.receive_net,
+            // What reached the vault, net of any mint_a transfer fee
+            remaining_deposit: self.vault.amount,
+            remaining_receive: receive,
+            expires_at: options.expires_at,
+            allowed_taker: options.allowed_taker,
+            allowed_takers_root: options.allowed_takers_root,
             bump,
         });
 
         Ok(())
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+    pub fn list_order(&mut self, bump: u8) -> Result<()> {
+        // The first maker on a pair creates its order book
+        if self.order_book.mint_a == Pubkey::default() {
+            self.order_book.set_inner(OrderBook {
+                mint_a: self.mint_a.key(),
+                mint_b: self.mint_b.key(),
+                orders: Vec::new(),
+                bump,
+            });
+        }
+
+        // Listing is best effort, so a full book never blocks a make
+        self.order_book.relist(self.escrow.key(), &self.escrow);
+
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, deposit: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(deposit > 0, EscrowError::InvalidAmount);
+
         let transfer_ctx = CpiContext::new(
             self.token_program.to_account_info(),
             TransferChecked {
@@ -70,10 +138,12 @@ impl<'info> Make<'info> {
                 to: self.vault.to_account_info(),
                 authority: self.maker.to_account_info(),
             },
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
 
         transfer_checked(transfer_ctx, deposit, self.mint_a.decimals)?;
 
-        Ok(())
+        self.vault.reload()
+
     }
 }
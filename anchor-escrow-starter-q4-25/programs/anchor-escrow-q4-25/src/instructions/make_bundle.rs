@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{transfer_checked, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    bundle,
+    errors::EscrowError,
+    state::{BundleEscrow, BundleLeg},
+};
+
+/// Remaining accounts: one `[mint, maker_ata, vault]` triple per offered leg, then one
+/// `[mint]` per requested leg.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"bundle", maker.key().as_ref(), &seed.to_le_bytes()],
+        space = BundleEscrow::DISCRIMINATOR.len() + BundleEscrow::INIT_SPACE,
+        bump,
+    )]
+    pub escrow: Account<'info, BundleEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MakeBundle<'info> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        offered: Vec<BundleLeg>,
+        requested: Vec<BundleLeg>,
+        bump: u8,
+    ) -> Result<()> {
+        BundleEscrow::validate_legs(&offered)?;
+        BundleEscrow::validate_legs(&requested)?;
+
+        self.escrow.set_inner(BundleEscrow {
+            seed,
+            maker: self.maker.key(),
+            offered,
+            requested,
+            bump,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let token_program = self.token_program.key();
+        let offered = self.escrow.offered.len();
+        let requested = self.escrow.requested.len();
+
+        require_eq!(
+            remaining_accounts.len(),
+            offered * bundle::ACCOUNTS_PER_LEG + requested,
+            EscrowError::InvalidBundleAccount
+        );
+
+        let leg_accounts = bundle::leg_accounts(remaining_accounts, 0, offered)?;
+        for (leg, accounts) in self.escrow.offered.iter().zip(leg_accounts) {
+            let [mint, maker_ata, vault] = accounts else {
+                unreachable!()
+            };
+
+            let decimals = bundle::load_mint(mint, leg, &token_program)?;
+            bundle::check_ata(maker_ata, &self.maker.key(), leg, &token_program)?;
+            bundle::check_ata(vault, &self.escrow.key(), leg, &token_program)?;
+
+            // Idempotent so a vault created ahead of time by anyone can't block the bundle
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token: vault.clone(),
+                    authority: self.escrow.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+
+            let transfer_ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: maker_ata.clone(),
+                    mint: mint.clone(),
+                    to: vault.clone(),
+                    authority: self.maker.to_account_info(),
+                },
+            );
+
+            transfer_checked(transfer_ctx, leg.amount, decimals)?;
+        }
+
+        // Requested mints only need to exist under the same token program
+        let requested_mints = &remaining_accounts[offered * bundle::ACCOUNTS_PER_LEG..];
+        for (leg, mint) in self.escrow.requested.iter().zip(requested_mints) {
+            bundle::load_mint(mint, leg, &token_program)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use mpl_core::{
+    instructions::{AddPluginV1CpiBuilder, TransferV1CpiBuilder},
+    types::{FreezeDelegate, Plugin, PluginAuthority, TransferDelegate},
+    ID as CORE_PROGRAM_ID,
+};
+
+use crate::{
+    errors::EscrowError,
+    nft,
+    state::{NftCustody, NftEscrow, NftPayment},
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeNft<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = asset.owner == &CORE_PROGRAM_ID @ EscrowError::InvalidAsset,
+        constraint = !asset.data_is_empty() @ EscrowError::InvalidAsset
+    )]
+    /// CHECK: Verified by mpl-core
+    pub asset: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = collection.owner == &CORE_PROGRAM_ID @ EscrowError::InvalidCollection,
+    )]
+    /// CHECK: Verified by mpl-core, only passed for assets in a collection
+    pub collection: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"nft_escrow", maker.key().as_ref(), &seed.to_le_bytes()],
+        space = NftEscrow::DISCRIMINATOR.len() + NftEscrow::INIT_SPACE,
+        bump,
+    )]
+    pub escrow: Account<'info, NftEscrow>,
+
+    #[account(address = CORE_PROGRAM_ID)]
+    /// CHECK: Verified by address constraint
+    pub core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeNft<'info> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        custody: NftCustody,
+        payment: NftPayment,
+        bump: u8,
+    ) -> Result<()> {
+        let (owner, collection) = nft::read_asset(&self.asset)?;
+        require_keys_eq!(owner, self.maker.key(), EscrowError::InvalidAsset);
+        nft::check_collection(
+            collection,
+            self.collection.as_ref().map(|c| c.as_ref()),
+        )?;
+
+        match payment {
+            NftPayment::Token { amount, .. } => {
+                require!(amount > 0, EscrowError::InvalidAmount)
+            }
+            NftPayment::Asset { asset } => {
+                require_keys_neq!(asset, self.asset.key(), EscrowError::InvalidPayment)
+            }
+        }
+
+        self.escrow.set_inner(NftEscrow {
+            seed,
+            maker: self.maker.key(),
+            asset: self.asset.key(),
+            collection,
+            custody,
+            payment,
+            bump,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit(&mut self) -> Result<()> {
+        let collection = self.collection.as_ref().map(|c| c.as_ref());
+
+        match self.escrow.custody {
+            NftCustody::Transfer => {
+                TransferV1CpiBuilder::new(&self.core_program.to_account_info())
+                    .asset(&self.asset.to_account_info())
+                    .collection(collection)
+                    .payer(&self.maker.to_account_info())
+                    .authority(Some(&self.maker.to_account_info()))
+                    .new_owner(&self.escrow.to_account_info())
+                    .system_program(Some(&self.system_program.to_account_info()))
+                    .invoke()?;
+            }
+            NftCustody::Freeze => {
+                AddPluginV1CpiBuilder::new(&self.core_program.to_account_info())
+                    .asset(&self.asset.to_account_info())
+                    .collection(collection)
+                    .payer(&self.maker.to_account_info())
+                    .authority(None)
+                    .plugin(Plugin::TransferDelegate(TransferDelegate {}))
+                    .init_authority(PluginAuthority::Address {
+                        address: self.escrow.key(),
+                    })
+                    .system_program(&self.system_program.to_account_info())
+                    .invoke()?;
+
+                AddPluginV1CpiBuilder::new(&self.core_program.to_account_info())
+                    .asset(&self.asset.to_account_info())
+                    .collection(collection)
+                    .payer(&self.maker.to_account_info())
+                    .authority(None)
+                    .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: true }))
+                    .init_authority(PluginAuthority::Address {
+                        address: self.escrow.key(),
+                    })
+                    .system_program(&self.system_program.to_account_info())
+                    .invoke()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,35 @@
 pub mod make;
 pub use make::*;
 
+pub mod init_order_book;
+pub use init_order_book::*;
+
 pub mod refund;
 pub use refund::*;
 
+pub mod refund_expired;
+pub use refund_expired::*;
+
 pub mod take;
 pub use take::*;
+
+pub mod update_escrow;
+pub use update_escrow::*;
+
+pub mod make_bundle;
+pub use make_bundle::*;
+
+pub mod take_bundle;
+pub use take_bundle::*;
+
+pub mod refund_bundle;
+pub use refund_bundle::*;
+
+pub mod make_nft;
+pub use make_nft::*;
+
+pub mod take_nft;
+pub use take_nft::*;
+
+pub mod refund_nft;
+pub use refund_nft::*;
@@ -1,19 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
-    },
+    token_interface::{CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::Escrow;
+use crate::{
+    state::{Escrow, OrderBook},
+    transfer::{close_vault, transfer_checked},
+};
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
 
+    #[account(mut)]
     pub mint_a: InterfaceAccount<'info, Mint>,
 
     #[account(
@@ -34,6 +35,13 @@ pub struct Refund<'info> {
     )]
     pub escrow: Account<'info, Escrow>,
 
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
     #[account(
         mut,
         associated_token::mint = mint_a,
@@ -48,7 +56,12 @@ pub struct Refund<'info> {
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    pub fn refund_and_close_vault(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        self.order_book.remove(&self.escrow.key());
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
@@ -65,7 +78,8 @@ impl<'info> Refund<'info> {
                 authority: self.escrow.to_account_info(),
             },
             &signer_seeds,
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
 
         let vault_balance = self.vault.amount;
 
@@ -81,7 +95,7 @@ impl<'info> Refund<'info> {
             &signer_seeds,
         );
 
-        close_account(close_ctx)?;
+        close_vault(close_ctx, self.mint_a.to_account_info())?;
 
         Ok(())
     }
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, CloseAccount, TokenInterface, TransferChecked};
+
+use crate::{bundle, errors::EscrowError, state::BundleEscrow, transfer::close_vault};
+
+/// Remaining accounts: one `[mint, vault, maker_ata]` triple per offered leg. Mints with
+/// a transfer fee must be writable so the vault's withheld fees can be harvested before
+/// it is closed.
+#[derive(Accounts)]
+pub struct RefundBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, BundleEscrow>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundBundle<'info> {
+    pub fn refund_and_close_vaults(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let token_program = self.token_program.key();
+        let offered = self.escrow.offered.len();
+
+        require_eq!(
+            remaining_accounts.len(),
+            offered * bundle::ACCOUNTS_PER_LEG,
+            EscrowError::InvalidBundleAccount
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ]];
+
+        let leg_accounts = bundle::leg_accounts(remaining_accounts, 0, offered)?;
+        for (leg, accounts) in self.escrow.offered.iter().zip(leg_accounts) {
+            let [mint, vault, maker_ata] = accounts else {
+                unreachable!()
+            };
+
+            let decimals = bundle::load_mint(mint, leg, &token_program)?;
+            bundle::check_ata(vault, &self.escrow.key(), leg, &token_program)?;
+            bundle::check_ata(maker_ata, &self.maker.key(), leg, &token_program)?;
+
+            let transfer_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    mint: mint.clone(),
+                    to: maker_ata.clone(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            );
+
+            transfer_checked(transfer_ctx, bundle::balance(vault)?, decimals)?;
+
+            let close_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: vault.clone(),
+                    destination: self.maker.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            );
+
+            close_vault(close_ctx, mint.clone())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::EscrowError,
+    state::{Escrow, OrderBook},
+    transfer::{close_vault, transfer_checked},
+};
+
+/// Permissionless refund of an expired escrow. Anyone (e.g. a keeper) can crank it;
+/// tokens go back to the maker's ATA and all rent is returned to the maker.
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundExpired<'info> {
+    pub fn refund_and_close_vault(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::EscrowNotExpired
+        );
+
+        self.order_book.remove(&self.escrow.key());
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: self.maker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            },
+            &signer_seeds,
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        let vault_balance = self.vault.amount;
+
+        transfer_checked(transfer_ctx, vault_balance, self.mint_a.decimals)?;
+
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault.to_account_info(),
+                destination: self.maker.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            },
+            &signer_seeds,
+        );
+
+        close_vault(close_ctx, self.mint_a.to_account_info())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use mpl_core::{
+    instructions::{RemovePluginV1CpiBuilder, TransferV1CpiBuilder, UpdatePluginV1CpiBuilder},
+    types::{FreezeDelegate, Plugin, PluginType},
+    ID as CORE_PROGRAM_ID,
+};
+
+use crate::{
+    nft,
+    state::{NftCustody, NftEscrow},
+};
+
+#[derive(Accounts)]
+pub struct RefundNft<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the escrow, verified by mpl-core
+    pub asset: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the escrow, verified by mpl-core
+    pub collection: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = asset,
+        seeds = [b"nft_escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, NftEscrow>,
+
+    #[account(address = CORE_PROGRAM_ID)]
+    /// CHECK: Verified by address constraint
+    pub core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundNft<'info> {
+    pub fn refund(&mut self) -> Result<()> {
+        let collection = self.collection.as_ref().map(|c| c.as_ref());
+        nft::check_collection(self.escrow.collection, collection)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"nft_escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ]];
+
+        match self.escrow.custody {
+            NftCustody::Transfer => {
+                TransferV1CpiBuilder::new(&self.core_program.to_account_info())
+                    .asset(&self.asset.to_account_info())
+                    .collection(collection)
+                    .payer(&self.maker.to_account_info())
+                    .authority(Some(&self.escrow.to_account_info()))
+                    .new_owner(&self.maker.to_account_info())
+                    .system_program(Some(&self.system_program.to_account_info()))
+                    .invoke_signed(&signer_seeds)?;
+            }
+            NftCustody::Freeze => {
+                UpdatePluginV1CpiBuilder::new(&self.core_program.to_account_info())
+                    .asset(&self.asset.to_account_info())
+                    .collection(collection)
+                    .payer(&self.maker.to_account_info())
+                    .authority(Some(&self.escrow.to_account_info()))
+                    .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
+                    .system_program(&self.system_program.to_account_info())
+                    .invoke_signed(&signer_seeds)?;
+
+                // The maker owns the asset, so it can drop both delegates itself
+                for plugin_type in [PluginType::FreezeDelegate, PluginType::TransferDelegate] {
+                    RemovePluginV1CpiBuilder::new(&self.core_program.to_account_info())
+                        .asset(&self.asset.to_account_info())
+                        .collection(collection)
+                        .payer(&self.maker.to_account_info())
+                        .authority(None)
+                        .plugin_type(plugin_type)
+                        .system_program(&self.system_program.to_account_info())
+                        .invoke()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
-    },
+    token_interface::{CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::Escrow;
+use crate::{
+    errors::EscrowError,
+    state::{Escrow, OrderBook},
+    transfer::{close_vault, gross_up, transfer_checked},
+};
 
 #[derive(Accounts)]
 pub struct Take<'info> {
@@ -16,6 +17,7 @@ pub struct Take<'info> {
     #[account(mut)]
     pub maker: SystemAccount<'info>,
 
+    #[account(mut)]
     pub mint_a: InterfaceAccount<'info, Mint>,
 
     pub mint_b: InterfaceAccount<'info, Mint>,
@@ -48,7 +50,6 @@ pub struct Take<'info> {
 
     #[account(
         mut,
-        close = maker,
         has_one = mint_a,
         has_one = mint_b,
         has_one = maker,
@@ -57,6 +58,13 @@ pub struct Take<'info> {
     )]
     pub escrow: Account<'info, Escrow>,
 
+    #[account(
+        mut,
+        seeds = [b"book", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
     #[account(
         mut,
         associated_token::mint = mint_a,
@@ -71,8 +79,33 @@ pub struct Take<'info> {
 }
 
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {
-        let amount = self.escrow.receive;
+    pub fn check_taker(&self, proof: &[[u8; 32]]) -> Result<()> {
+        require_keys_neq!(self.taker.key(), self.maker.key(), EscrowError::MakerIsTaker);
+        require!(
+            self.escrow.is_allowed_taker(&self.taker.key(), proof),
+            EscrowError::TakerNotAllowed
+        );
+
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            !self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::EscrowExpired
+        );
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(
+            amount <= self.escrow.remaining_receive,
+            EscrowError::FillExceedsRemaining
+        );
+        if !self.escrow.partial_fill {
+            require_eq!(
+                amount,
+                self.escrow.remaining_receive,
+                EscrowError::PartialFillDisabled
+            );
+        }
 
         let transfer_ctx = CpiContext::new(
             self.token_program.to_account_info(),
@@ -82,14 +115,28 @@ impl<'info> Take<'info> {
                 to: self.maker_ata_b.to_account_info(),
                 authority: self.taker.to_account_info(),
             },
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        let payment = if self.escrow.receive_net {
+            gross_up(&self.mint_b, amount)?
+        } else {
+            amount
+        };
 
-        transfer_checked(transfer_ctx, amount, self.mint_b.decimals)?;
+        transfer_checked(transfer_ctx, payment, self.mint_b.decimals)?;
 
         Ok(())
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    pub fn withdraw_and_close_vault(
+        &mut self,
+        amount: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let release = self.escrow.release_for(amount)?;
+        require!(release > 0, EscrowError::FillTooSmall);
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
@@ -106,11 +153,29 @@ impl<'info> Take<'info> {
                 authority: self.escrow.to_account_info(),
             },
             &signer_seeds,
-        );
-
-        let vault_balance = self.vault.amount;
-
-        transfer_checked(transfer_ctx, vault_balance, self.mint_a.decimals)?;
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(transfer_ctx, release, self.mint_a.decimals)?;
+
+        self.escrow.remaining_deposit = self
+            .escrow
+            .remaining_deposit
+            .checked_sub(release)
+            .ok_or(EscrowError::Overflow)?;
+        self.escrow.remaining_receive = self
+            .escrow
+            .remaining_receive
+            .checked_sub(amount)
+            .ok_or(EscrowError::Overflow)?;
+
+        // Keep the order open until it is fully filled
+        if !self.escrow.is_filled() {
+            self.order_book.relist(self.escrow.key(), &self.escrow);
+            return Ok(());
+        }
+
+        self.order_book.remove(&self.escrow.key());
 
         let close_ctx = CpiContext::new_with_signer(
             self.token_program.to_account_info(),
@@ -122,7 +187,9 @@ impl<'info> Take<'info> {
             &signer_seeds,
         );
 
-        close_account(close_ctx)?;
+        close_vault(close_ctx, self.mint_a.to_account_info())?;
+
+        self.escrow.close(self.maker.to_account_info())?;
 
         Ok(())
     }
@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, CloseAccount, TokenInterface, TransferChecked};
+
+use crate::{bundle, errors::EscrowError, state::BundleEscrow, transfer::close_vault};
+
+/// Remaining accounts: one `[mint, vault, taker_ata]` triple per offered leg, then one
+/// `[mint, taker_ata, maker_ata]` triple per requested leg. All token accounts must
+/// already exist. Offered mints with a transfer fee must be writable so the vault's
+/// withheld fees can be harvested before it is closed.
+#[derive(Accounts)]
+pub struct TakeBundle<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"bundle", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, BundleEscrow>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> TakeBundle<'info> {
+    pub fn deposit(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let token_program = self.token_program.key();
+        let offered = self.escrow.offered.len();
+        let requested = self.escrow.requested.len();
+
+        require_eq!(
+            remaining_accounts.len(),
+            (offered + requested) * bundle::ACCOUNTS_PER_LEG,
+            EscrowError::InvalidBundleAccount
+        );
+
+        let leg_accounts = bundle::leg_accounts(remaining_accounts, offered, requested)?;
+        for (leg, accounts) in self.escrow.requested.iter().zip(leg_accounts) {
+            let [mint, taker_ata, maker_ata] = accounts else {
+                unreachable!()
+            };
+
+            let decimals = bundle::load_mint(mint, leg, &token_program)?;
+            bundle::check_ata(taker_ata, &self.taker.key(), leg, &token_program)?;
+            bundle::check_ata(maker_ata, &self.maker.key(), leg, &token_program)?;
+
+            let transfer_ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: taker_ata.clone(),
+                    mint: mint.clone(),
+                    to: maker_ata.clone(),
+                    authority: self.taker.to_account_info(),
+                },
+            );
+
+            transfer_checked(transfer_ctx, leg.amount, decimals)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw_and_close_vaults(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let token_program = self.token_program.key();
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ]];
+
+        let leg_accounts =
+            bundle::leg_accounts(remaining_accounts, 0, self.escrow.offered.len())?;
+        for (leg, accounts) in self.escrow.offered.iter().zip(leg_accounts) {
+            let [mint, vault, taker_ata] = accounts else {
+                unreachable!()
+            };
+
+            let decimals = bundle::load_mint(mint, leg, &token_program)?;
+            bundle::check_ata(vault, &self.escrow.key(), leg, &token_program)?;
+            bundle::check_ata(taker_ata, &self.taker.key(), leg, &token_program)?;
+
+            let transfer_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    mint: mint.clone(),
+                    to: taker_ata.clone(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            );
+
+            transfer_checked(transfer_ctx, bundle::balance(vault)?, decimals)?;
+
+            let close_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: vault.clone(),
+                    destination: self.maker.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            );
+
+            close_vault(close_ctx, mint.clone())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+use mpl_core::{
+    instructions::{TransferV1CpiBuilder, UpdatePluginV1CpiBuilder},
+    types::{FreezeDelegate, Plugin},
+    ID as CORE_PROGRAM_ID,
+};
+
+use crate::{
+    errors::EscrowError,
+    nft,
+    state::{NftCustody, NftEscrow, NftPayment},
+};
+
+/// Token payments need `payment_mint`, `taker_payment_ata`, `maker_payment_ata` and the
+/// token programs; asset payments need `payment_asset` and, for assets in a collection,
+/// `payment_collection`.
+#[derive(Accounts)]
+pub struct TakeNft<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the escrow, verified by mpl-core
+    pub asset: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the escrow, verified by mpl-core
+    pub collection: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = asset,
+        seeds = [b"nft_escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, NftEscrow>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub taker_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub maker_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the escrow, verified by mpl-core
+    pub payment_asset: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the payment asset, verified by mpl-core
+    pub payment_collection: Option<UncheckedAccount<'info>>,
+
+    #[account(address = CORE_PROGRAM_ID)]
+    /// CHECK: Verified by address constraint
+    pub core_program: UncheckedAccount<'info>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeNft<'info> {
+    pub fn pay(&mut self) -> Result<()> {
+        match self.escrow.payment.clone() {
+            NftPayment::Token { mint, amount } => self.pay_token(mint, amount),
+            NftPayment::Asset { asset } => self.pay_asset(asset),
+        }
+    }
+
+    fn pay_token(&self, mint: Pubkey, amount: u64) -> Result<()> {
+        let (
+            Some(payment_mint),
+            Some(taker_payment_ata),
+            Some(maker_payment_ata),
+            Some(token_program),
+        ) = (
+            &self.payment_mint,
+            &self.taker_payment_ata,
+            &self.maker_payment_ata,
+            &self.token_program,
+        )
+        else {
+            return err!(EscrowError::InvalidPayment);
+        };
+
+        require_keys_eq!(payment_mint.key(), mint, EscrowError::InvalidPayment);
+        require_keys_eq!(
+            *payment_mint.to_account_info().owner,
+            token_program.key(),
+            EscrowError::InvalidPayment
+        );
+        require_keys_eq!(taker_payment_ata.owner, self.taker.key(), EscrowError::InvalidPayment);
+        require_keys_eq!(maker_payment_ata.owner, self.maker.key(), EscrowError::InvalidPayment);
+        require_keys_eq!(maker_payment_ata.mint, mint, EscrowError::InvalidPayment);
+
+        let transfer_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: taker_payment_ata.to_account_info(),
+                mint: payment_mint.to_account_info(),
+                to: maker_payment_ata.to_account_info(),
+                authority: self.taker.to_account_info(),
+            },
+        );
+
+        transfer_checked(transfer_ctx, amount, payment_mint.decimals)
+    }
+
+    fn pay_asset(&self, asset: Pubkey) -> Result<()> {
+        let Some(payment_asset) = &self.payment_asset else {
+            return err!(EscrowError::InvalidPayment);
+        };
+        require_keys_eq!(payment_asset.key(), asset, EscrowError::InvalidPayment);
+
+        let (_, collection) = nft::read_asset(payment_asset)?;
+        let payment_collection = self.payment_collection.as_ref().map(|c| c.as_ref());
+        nft::check_collection(collection, payment_collection)?;
+
+        TransferV1CpiBuilder::new(&self.core_program.to_account_info())
+            .asset(&payment_asset.to_account_info())
+            .collection(payment_collection)
+            .payer(&self.taker.to_account_info())
+            .authority(Some(&self.taker.to_account_info()))
+            .new_owner(&self.maker.to_account_info())
+            .system_program(Some(&self.system_program.to_account_info()))
+            .invoke()?;
+
+        Ok(())
+    }
+
+    pub fn release(&mut self) -> Result<()> {
+        let collection = self.collection.as_ref().map(|c| c.as_ref());
+        nft::check_collection(self.escrow.collection, collection)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"nft_escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ]];
+
+        if self.escrow.custody == NftCustody::Freeze {
+            UpdatePluginV1CpiBuilder::new(&self.core_program.to_account_info())
+                .asset(&self.asset.to_account_info())
+                .collection(collection)
+                .payer(&self.taker.to_account_info())
+                .authority(Some(&self.escrow.to_account_info()))
+                .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
+                .system_program(&self.system_program.to_account_info())
+                .invoke_signed(&signer_seeds)?;
+        }
+
+        // In freeze custody the escrow moves the asset as its transfer delegate
+        TransferV1CpiBuilder::new(&self.core_program.to_account_info())
+            .asset(&self.asset.to_account_info())
+            .collection(collection)
+            .payer(&self.taker.to_account_info())
+            .authority(Some(&self.escrow.to_account_info()))
+            .new_owner(&self.taker.to_account_info())
+            .system_program(Some(&self.system_program.to_account_info()))
+            .invoke_signed(&signer_seeds)?;
+
+        Ok(())
+    }
+}
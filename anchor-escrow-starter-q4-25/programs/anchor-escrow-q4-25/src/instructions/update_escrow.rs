@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::EscrowError,
+    events::EscrowUpdated,
+    state::{Escrow, OrderBook},
+    transfer::transfer_checked,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum DepositChange {
+    TopUp(u64),
+    Withdraw(u64),
+}
+
+#[derive(Accounts)]
+pub struct UpdateEscrow<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// Only passed when the maker wants to be paid in a different mint.
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub new_mint_b: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    /// Order book of the new pair, only passed together with `new_mint_b`.
+    #[account(
+        mut,
+        constraint = new_order_book.key() != order_book.key() @ EscrowError::InvalidOrderBook,
+    )]
+    pub new_order_book: Option<Account<'info, OrderBook>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateEscrow<'info> {
+    pub fn update_terms(&mut self, receive: Option<u64>) -> Result<()> {
+        // Repricing a half-filled order would change the deal for earlier takers
+        require!(
+            self.escrow.remaining_receive == self.escrow.receive,
+            EscrowError::TakeInProgress
+        );
+
+        if let Some(receive) = receive {
+            require!(receive > 0, EscrowError::InvalidAmount);
+            self.escrow.receive = receive;
+            self.escrow.remaining_receive = receive;
+        }
+
+        if let Some(new_mint_b) = &self.new_mint_b {
+            self.escrow.mint_b = new_mint_b.key();
+        }
+
+        Ok(())
+    }
+
+    /// Remaining accounts are forwarded to mint_a's transfer hook, if it has one.
+    pub fn update_deposit(
+        &mut self,
+        deposit_change: Option<DepositChange>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        match deposit_change {
+            Some(DepositChange::TopUp(amount)) => {
+                require!(amount > 0, EscrowError::InvalidAmount);
+
+                let transfer_ctx = CpiContext::new(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: self.maker_ata_a.to_account_info(),
+                        mint: self.mint_a.to_account_info(),
+                        to: self.vault.to_account_info(),
+                        authority: self.maker.to_account_info(),
+                    },
+                )
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+                transfer_checked(transfer_ctx, amount, self.mint_a.decimals)?;
+
+                // What reached the vault, net of any mint_a transfer fee
+                self.vault.reload()?;
+                self.escrow.remaining_deposit = self.vault.amount;
+            }
+            Some(DepositChange::Withdraw(amount)) => {
+                // Withdrawing everything is a refund
+                require!(
+                    amount > 0 && amount < self.escrow.remaining_deposit,
+                    EscrowError::InvalidAmount
+                );
+
+                let signer_seeds: [&[&[u8]]; 1] = [&[
+                    b"escrow",
+                    self.maker.to_account_info().key.as_ref(),
+                    &self.escrow.seed.to_le_bytes(),
+                    &[self.escrow.bump],
+                ]];
+
+                let transfer_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: self.vault.to_account_info(),
+                        mint: self.mint_a.to_account_info(),
+                        to: self.maker_ata_a.to_account_info(),
+                        authority: self.escrow.to_account_info(),
+                    },
+                    &signer_seeds,
+                )
+                .with_remaining_accounts(remaining_accounts.to_vec());
+
+                transfer_checked(transfer_ctx, amount, self.mint_a.decimals)?;
+
+                self.escrow.remaining_deposit = self
+                    .escrow
+                    .remaining_deposit
+                    .checked_sub(amount)
+                    .ok_or(EscrowError::Overflow)?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn reindex(&mut self) -> Result<()> {
+        let escrow = self.escrow.key();
+
+        if self.escrow.mint_b == self.order_book.mint_b {
+            self.order_book.relist(escrow, &self.escrow);
+            return Ok(());
+        }
+
+        let Some(new_order_book) = &mut self.new_order_book else {
+            return err!(EscrowError::InvalidOrderBook);
+        };
+        require!(
+            new_order_book.mint_a == self.escrow.mint_a
+                && new_order_book.mint_b == self.escrow.mint_b,
+            EscrowError::InvalidOrderBook
+        );
+
+        self.order_book.remove(&escrow);
+        new_order_book.relist(escrow, &self.escrow);
+
+        Ok(())
+    }
+
+    pub fn emit_updated(&self) -> Result<()> {
+        emit!(EscrowUpdated {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            mint_b: self.escrow.mint_b,
+            remaining_deposit: self.escrow.remaining_deposit,
+            receive: self.escrow.receive,
+        });
+
+        Ok(())
+    }
+}
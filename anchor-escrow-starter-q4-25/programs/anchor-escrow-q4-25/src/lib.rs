@@ -1,8 +1,16 @@
 use anchor_lang::prelude::*;
 
+pub mod bundle;
+pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod merkle;
+pub mod nft;
 pub mod state;
+pub mod transfer;
 
+pub use errors::*;
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -11,17 +19,96 @@ declare_id!("8z5GGyDAQ5j1jd1qpfbtLyikhgzHyQKxgeNxQdPvLaZV");
 #[program]
 pub mod anchor_escrow_q4_25 {
     use super::*;
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
-        ctx.accounts.deposit(deposit)?;
-        ctx.accounts.init_escrow(seed, receive, ctx.bumps.escrow)
+    pub fn make<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Make<'info>>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        options: MakeOptions,
+    ) -> Result<()> {
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        ctx.accounts
+            .init_escrow(seed, receive, options, ctx.bumps.escrow)?;
+        ctx.accounts.list_order(ctx.bumps.order_book)
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn init_order_book(ctx: Context<InitOrderBook>) -> Result<()> {
+        ctx.accounts.init_order_book(ctx.bumps.order_book)
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+    pub fn refund<'info>(ctx: Context<'_, '_, 'info, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
+    }
+
+    pub fn refund_expired<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundExpired<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
+    }
+
+    pub fn take<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Take<'info>>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.check_taker(&proof)?;
+        ctx.accounts.deposit(amount, ctx.remaining_accounts)?;
+        ctx.accounts
+            .withdraw_and_close_vault(amount, ctx.remaining_accounts)
+    }
+
+    pub fn update_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateEscrow<'info>>,
+        receive: Option<u64>,
+        deposit_change: Option<DepositChange>,
+    ) -> Result<()> {
+        ctx.accounts.update_terms(receive)?;
+        ctx.accounts.update_deposit(deposit_change, ctx.remaining_accounts)?;
+        ctx.accounts.reindex()?;
+        ctx.accounts.emit_updated()
+    }
+
+    pub fn make_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBundle<'info>>,
+        seed: u64,
+        offered: Vec<BundleLeg>,
+        requested: Vec<BundleLeg>,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_escrow(seed, offered, requested, ctx.bumps.escrow)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    pub fn take_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeBundle<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.deposit(ctx.remaining_accounts)?;
+        ctx.accounts.withdraw_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    pub fn refund_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBundle<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    pub fn make_nft(
+        ctx: Context<MakeNft>,
+        seed: u64,
+        custody: NftCustody,
+        payment: NftPayment,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_escrow(seed, custody, payment, ctx.bumps.escrow)?;
+        ctx.accounts.deposit()
+    }
+
+    pub fn take_nft(ctx: Context<TakeNft>) -> Result<()> {
+        ctx.accounts.pay()?;
+        ctx.accounts.release()
+    }
+
+    pub fn refund_nft(ctx: Context<RefundNft>) -> Result<()> {
+        ctx.accounts.refund()
     }
 }
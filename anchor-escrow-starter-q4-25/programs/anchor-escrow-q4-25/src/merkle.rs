@@ -0,0 +1,24 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+// Leaves and inner nodes are hashed with different prefixes so an inner node can
+// never be passed off as a leaf.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf(taker: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, taker.as_ref()]).to_bytes()
+}
+
+/// Hashes a pair of nodes in sorted order, so proofs do not need to carry the
+/// left/right position of each sibling.
+pub fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |current, sibling| node(&current, sibling));
+    &computed == root
+}
@@ -0,0 +1,30 @@
+//! Helpers for escrows holding mpl-core assets.
+
+use anchor_lang::prelude::*;
+use mpl_core::{accounts::BaseAssetV1, types::UpdateAuthority};
+
+use crate::errors::EscrowError;
+
+/// Returns the owner of an mpl-core asset and the collection it belongs to, if any.
+pub fn read_asset(asset: &AccountInfo) -> Result<(Pubkey, Option<Pubkey>)> {
+    let asset = BaseAssetV1::from_bytes(&asset.data.borrow())
+        .map_err(|_| error!(EscrowError::InvalidAsset))?;
+
+    let collection = match asset.update_authority {
+        UpdateAuthority::Collection(collection) => Some(collection),
+        _ => None,
+    };
+
+    Ok((asset.owner, collection))
+}
+
+/// Checks that the collection account passed alongside an asset is the one the asset
+/// belongs to, or that none is passed for assets outside a collection.
+pub fn check_collection(expected: Option<Pubkey>, collection: Option<&AccountInfo>) -> Result<()> {
+    require!(
+        expected == collection.map(|collection| collection.key()),
+        EscrowError::InvalidCollection
+    );
+
+    Ok(())
+}
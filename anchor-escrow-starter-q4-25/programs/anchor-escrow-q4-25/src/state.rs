@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::{errors::EscrowError, merkle};
+
 #[account]
 #[derive(InitSpace)]
 pub struct Escrow {
@@ -8,5 +10,224 @@ pub struct Escrow {
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
     pub receive: u64,
+    pub partial_fill: bool,
+    /// The taker's payment is grossed up for mint_b's transfer fee so the maker
+    /// receives exactly `receive`.
+    pub receive_net: bool,
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+    pub allowed_takers_root: Option<[u8; 32]>,
+    pub bump: u8,
+}
+
+impl Escrow {
+    /// Amount of mint_a released for paying `amount_b` of mint_b, pro rata to what is
+    /// still open. The last fill always releases the whole remaining deposit.
+    pub fn release_for(&self, amount_b: u64) -> Result<u64> {
+        if amount_b == self.remaining_receive {
+            return Ok(self.remaining_deposit);
+        }
+
+        let release = (self.remaining_deposit as u128)
+            .checked_mul(amount_b as u128)
+            .ok_or(EscrowError::Overflow)?
+            .checked_div(self.remaining_receive as u128)
+            .ok_or(EscrowError::Overflow)?;
+
+        u64::try_from(release).map_err(|_| EscrowError::Overflow.into())
+    }
+
+    /// Order book entry for what is still open on this escrow, if anything is.
+    pub fn order(&self, escrow: Pubkey) -> Option<Order> {
+        Order::new(escrow, self.remaining_deposit, self.remaining_receive)
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.remaining_receive == 0
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Open escrows accept any taker. Otherwise the taker must be the designated
+    /// counterparty or prove membership in the takers Merkle tree.
+    pub fn is_allowed_taker(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        match (self.allowed_taker, self.allowed_takers_root) {
+            (Some(allowed_taker), _) => &allowed_taker == taker,
+            (None, Some(root)) => merkle::verify(proof, &root, merkle::leaf(taker)),
+            (None, None) => true,
+        }
+    }
+}
+
+/// Maximum number of open escrows listed in one order book.
+pub const MAX_ORDERS: usize = 32;
+
+/// Fixed-point scale of `Order::price`.
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Order {
+    pub escrow: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    /// Base units of mint_b asked per base unit of mint_a, scaled by `PRICE_SCALE`.
+    /// Any u64 ratio fits, so pricing never fails.
+    pub price: u128,
+}
+
+impl Order {
+    /// `None` when nothing is left to sell.
+    pub fn new(escrow: Pubkey, deposit: u64, receive: u64) -> Option<Self> {
+        let price = (receive as u128 * PRICE_SCALE).checked_div(deposit as u128)?;
+
+        Some(Self {
+            escrow,
+            deposit,
+            receive,
+            price,
+        })
+    }
+}
+
+/// Index of the open escrows selling mint_a for mint_b, so takers can find offers
+/// without scanning program accounts. Indexing is best effort: once the book is full
+/// only the `MAX_ORDERS` cheapest offers are listed, and escrows stay tradable either way.
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    #[max_len(MAX_ORDERS)]
+    pub orders: Vec<Order>,
+    pub bump: u8,
+}
+
+impl OrderBook {
+    /// Lists `order`, replacing the entry of the same escrow if there is one. When the
+    /// book is full it evicts the most expensive listing if `order` is cheaper, and is
+    /// left out otherwise. Returns whether `order` is listed.
+    pub fn upsert(&mut self, order: Order) -> bool {
+        if let Some(listed) = self.orders.iter_mut().find(|o| o.escrow == order.escrow) {
+            *listed = order;
+            return true;
+        }
+
+        if self.orders.len() < MAX_ORDERS {
+            self.orders.push(order);
+            return true;
+        }
+
+        let Some((worst, listed)) = self
+            .orders
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.price.cmp(&b.price).then(b.deposit.cmp(&a.deposit)))
+        else {
+            return false;
+        };
+        if order.price >= listed.price {
+            return false;
+        }
+
+        self.orders[worst] = order;
+        true
+    }
+
+    /// Relists what is still open on `escrow`, or drops its listing if nothing is.
+    pub fn relist(&mut self, key: Pubkey, escrow: &Escrow) -> bool {
+        match escrow.order(key) {
+            Some(order) => self.upsert(order),
+            None => {
+                self.remove(&key);
+                false
+            }
+        }
+    }
+
+    pub fn remove(&mut self, escrow: &Pubkey) {
+        self.orders.retain(|order| &order.escrow != escrow);
+    }
+
+    /// Up to `limit` offers, cheapest first. Equal prices favour the larger deposit.
+    pub fn best_offers(&self, limit: usize) -> Vec<Order> {
+        let mut offers = self.orders.clone();
+        offers.sort_by(|a, b| a.price.cmp(&b.price).then(b.deposit.cmp(&a.deposit)));
+        offers.truncate(limit);
+        offers
+    }
+}
+
+/// Maximum number of mints on each side of a bundle escrow.
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct BundleLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Atomic OTC bundle trade: every `offered` leg sits in an escrow-owned vault and is
+/// released only when the taker pays every `requested` leg in the same instruction.
+#[account]
+#[derive(InitSpace)]
+pub struct BundleEscrow {
+    pub seed: u64,
+    pub maker: Pubkey,
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub offered: Vec<BundleLeg>,
+    #[max_len(MAX_BUNDLE_LEGS)]
+    pub requested: Vec<BundleLeg>,
+    pub bump: u8,
+}
+
+impl BundleEscrow {
+    pub fn validate_legs(legs: &[BundleLeg]) -> Result<()> {
+        require!(
+            !legs.is_empty() && legs.len() <= MAX_BUNDLE_LEGS,
+            EscrowError::InvalidBundle
+        );
+
+        for (i, leg) in legs.iter().enumerate() {
+            require!(leg.amount > 0, EscrowError::InvalidAmount);
+            require!(
+                legs[..i].iter().all(|other| other.mint != leg.mint),
+                EscrowError::InvalidBundle
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// How the maker's mpl-core asset is held while an NFT escrow is open.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum NftCustody {
+    /// The asset is transferred to the escrow PDA.
+    Transfer,
+    /// The asset stays in the maker's wallet, frozen with a `FreezeDelegate` and
+    /// movable through a `TransferDelegate`, both owned by the escrow PDA.
+    Freeze,
+}
+
+/// What the taker pays for the escrowed asset.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum NftPayment {
+    Token { mint: Pubkey, amount: u64 },
+    Asset { asset: Pubkey },
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct NftEscrow {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub asset: Pubkey,
+    pub collection: Option<Pubkey>,
+    pub custody: NftCustody,
+    pub payment: NftPayment,
     pub bump: u8,
 }
@@ -0,0 +1,88 @@
+//! Token transfers that also work for Token-2022 transfer-fee and transfer-hook mints.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        onchain,
+        state::{Account as AccountState, Mint as MintState},
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{close_account, CloseAccount, Mint, TransferChecked},
+};
+
+use crate::errors::EscrowError;
+
+/// Drop-in for `token_interface::transfer_checked` that forwards the context's remaining
+/// accounts to the mint's transfer hook. Extra accounts are matched by key, so the
+/// accounts of several hooked mints can be passed together.
+pub fn transfer_checked<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    onchain::invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+/// Amount to send so that `net` arrives after the mint's transfer fee for the current
+/// epoch. Mints without a transfer fee return `net` unchanged.
+pub fn gross_up(mint: &InterfaceAccount<Mint>, net: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let mint_data = mint_info.data.borrow();
+    let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(net);
+    };
+
+    fee_config
+        .get_epoch_fee(Clock::get()?.epoch)
+        .calculate_pre_fee_amount(net)
+        .ok_or(EscrowError::Overflow.into())
+}
+
+/// Drop-in for `token_interface::close_account` for escrow vaults. Token-2022 refuses to
+/// close an account holding withheld transfer fees, so any are first harvested to
+/// `mint`, which must then be writable.
+pub fn close_vault<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CloseAccount<'info>>,
+    mint: AccountInfo<'info>,
+) -> Result<()> {
+    if withheld_fees(&ctx.accounts.account)? > 0 {
+        let harvest_ctx = CpiContext::new(
+            ctx.program.clone(),
+            HarvestWithheldTokensToMint {
+                token_program_id: ctx.program.clone(),
+                mint,
+            },
+        );
+
+        harvest_withheld_tokens_to_mint(harvest_ctx, vec![ctx.accounts.account.clone()])?;
+    }
+
+    close_account(ctx)
+}
+
+/// Transfer fees withheld in a token account. Accounts without the extension hold none.
+fn withheld_fees(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.data.borrow();
+    let account_state = StateWithExtensions::<AccountState>::unpack(&data)?;
+
+    Ok(account_state
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |fee_amount| u64::from(fee_amount.withheld_amount)))
+}
@@ -0,0 +1,115 @@
+//! Minimal Token-2022 transfer hook that counts the transfers of a mint. It needs one
+//! extra account, the mint's `Counter` PDA, so an escrow transfer only succeeds when
+//! the extra accounts are forwarded.
+
+use anchor_lang::prelude::*;
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("DLcJN5NpN3m6CyfeKDWQeACqM5igvxYz59jrVpDKQV1y");
+
+#[program]
+pub mod transfer_hook_counter {
+    use super::*;
+
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        ctx.accounts.initialize()
+    }
+
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        ctx.accounts.counter.transfers += 1;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Counter {
+    pub transfers: u64,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Any mint, only used as a seed
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        space = ExtraAccountMetaList::size_of(1).unwrap(),
+        bump,
+    )]
+    /// CHECK: Initialized below as a TLV extra account meta list
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"counter", mint.key().as_ref()],
+        space = Counter::DISCRIMINATOR.len() + Counter::INIT_SPACE,
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeExtraAccountMetaList<'info> {
+    pub fn initialize(&mut self) -> Result<()> {
+        // The counter is derived from the mint, which is account 1 of `Execute`
+        let extra_account_metas = [ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"counter".to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )?];
+
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut self.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    /// CHECK: Source token account
+    pub source: UncheckedAccount<'info>,
+
+    /// CHECK: Mint being transferred
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Destination token account
+    pub destination: UncheckedAccount<'info>,
+
+    /// CHECK: Source owner or delegate
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Extra account meta list of the mint
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+}
@@ -25,10 +25,20 @@ describe("anchor_escrow_q4_25", () => {
   let escrowPda: anchor.web3.PublicKey;
   let escrowBump: number;
   let vault: anchor.web3.PublicKey;
+  let orderBook: anchor.web3.PublicKey;
 
   const depositAmount = 100;
   const receiveAmount = 200;
 
+  // Plain all-or-nothing swap, open to any taker, no expiry
+  const makeOptions = {
+    partialFill: false,
+    receiveNet: false,
+    expiresAt: null,
+    allowedTaker: null,
+    allowedTakersRoot: null,
+  };
+
   before(async () => {
     // Airdrop SOL to maker and taker
     await provider.connection.requestAirdrop(maker.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
@@ -54,6 +64,11 @@ describe("anchor_escrow_q4_25", () => {
     await provider.sendAndConfirm(takerAtaBTx, [taker]);
     await mintTo(provider.connection, taker, mintB, takerAtaB, taker, receiveAmount * 2);
 
+    [orderBook] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("book"), mintA.toBuffer(), mintB.toBuffer()],
+      program.programId
+    );
+
   });
 
   it("Makes and refunds the escrow", async () => {
@@ -66,13 +81,14 @@ describe("anchor_escrow_q4_25", () => {
 
     // Make
     await program.methods
-      .make(seed1, new anchor.BN(depositAmount), new anchor.BN(receiveAmount))
+      .make(seed1, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), makeOptions)
       .accountsStrict({
         maker: maker.publicKey,
         mintA: mintA,
         mintB: mintB,
         makerAtaA: makerAtaA,
         escrow: escrowPda,
+        orderBook: orderBook,
         vault: vault,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -91,6 +107,9 @@ describe("anchor_escrow_q4_25", () => {
     const vaultBalance = (await provider.connection.getTokenAccountBalance(vault)).value.uiAmount;
     expect(vaultBalance).to.equal(depositAmount);
 
+    const orderBookAccount = await program.account.orderBook.fetch(orderBook);
+    expect(orderBookAccount.orders.map((order) => order.escrow.toBase58())).to.deep.equal([escrowPda.toBase58()]);
+
     // Refund
     await program.methods
       .refund()
@@ -99,6 +118,7 @@ describe("anchor_escrow_q4_25", () => {
         mintA: mintA,
         makerAtaA: makerAtaA,
         escrow: escrowPda,
+        orderBook: orderBook,
         vault: vault,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -113,6 +133,8 @@ describe("anchor_escrow_q4_25", () => {
 
     const vaultInfo = await provider.connection.getAccountInfo(vault);
     expect(vaultInfo).to.be.null;
+
+    expect((await program.account.orderBook.fetch(orderBook)).orders).to.be.empty;
   });
 
   it("Makes and takes the escrow", async () => {
@@ -125,13 +147,14 @@ describe("anchor_escrow_q4_25", () => {
 
     // Make (again for take path)
     await program.methods
-      .make(seed2, new anchor.BN(depositAmount), new anchor.BN(receiveAmount))
+      .make(seed2, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), makeOptions)
       .accountsStrict({
         maker: maker.publicKey,
         mintA: mintA,
         mintB: mintB,
         makerAtaA: makerAtaA,
         escrow: escrowPda,
+        orderBook: orderBook,
         vault: vault,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -146,7 +169,7 @@ describe("anchor_escrow_q4_25", () => {
 
     // Take
     await program.methods
-      .take()
+      .take(new anchor.BN(receiveAmount), [])
       .accountsStrict({
         taker: taker.publicKey,
         maker: maker.publicKey,
@@ -156,6 +179,7 @@ describe("anchor_escrow_q4_25", () => {
         takerAtaB: takerAtaB,
         makerAtaB: makerAtaB,
         escrow: escrowPda,
+        orderBook: orderBook,
         vault: vault,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -1,14 +1,34 @@
-use anchor_escrow_q4_25::{accounts, instruction};
-use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_escrow_q4_25::{
+    accounts, instruction, merkle, BundleLeg, DepositChange, Escrow, EscrowError, MakeOptions,
+    NftCustody, NftPayment, Order, OrderBook, MAX_ORDERS, PRICE_SCALE,
+};
+use anchor_escrow_client::{
+    decode_escrow, make_ix, order_book_address, refund_ix, take_ix, vault_address,
+};
+use anchor_lang::{error::ErrorCode, system_program, AccountDeserialize, InstructionData, ToAccountMetas};
 use litesvm::LiteSVM;
 use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, signature::{read_keypair_file, Keypair, Signer}, transaction::Transaction
+    clock::Clock, instruction::{AccountMeta, Instruction, InstructionError}, pubkey::Pubkey, signature::{read_keypair_file, Keypair, Signer}, transaction::{Transaction, TransactionError}
 };
 use litesvm_token::{
     spl_token::{self, native_mint::DECIMALS},
     CreateAssociatedTokenAccount, CreateMint, MintTo,
 };
-use spl_associated_token_account::get_associated_token_address;
+use mpl_core::{
+    accounts::BaseAssetV1,
+    instructions::{CreateV2Builder, TransferV1Builder},
+};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{self, TransferFeeConfig},
+        transfer_hook, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+};
+use proptest::prelude::*;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
 
 #[test]
 fn test_make_and_refund() {
@@ -75,23 +95,25 @@ fn test_make_and_refund() {
 
     let make_accounts = accounts::Make {
         maker: maker.pubkey(),
-        mint_a: mint_a,
-        mint_b: mint_b,
-        maker_ata_a: maker_ata_a,
-        escrow: escrow,
-        vault: vault,
+        mint_a,
+        mint_b,
+        maker_ata_a,
+        escrow,
+        order_book: order_book_address(&mint_a, &mint_b),
+        vault,
         associated_token_program: anchor_spl::associated_token::ID,
         system_program: system_program::ID,
         token_program: anchor_spl::token::ID,
     };
     let make_data = instruction::Make {
-        seed: seed,
-        deposit: deposit,
-        receive: receive,
+        seed,
+        deposit,
+        receive,
+        options: MakeOptions::default(),
     };
 
     let make_instruction = Instruction {
-        program_id: program_id,
+        program_id,
         accounts: make_accounts.to_account_metas(None),
         data: make_data.data(),
     };
@@ -111,10 +133,11 @@ fn test_make_and_refund() {
 
     let refund_accounts = accounts::Refund {
         maker: maker.pubkey(),
-        mint_a: mint_a,
-        maker_ata_a: maker_ata_a,
-        escrow: escrow,
-        vault: vault,
+        mint_a,
+        maker_ata_a,
+        escrow,
+        order_book: order_book_address(&mint_a, &mint_b),
+        vault,
         associated_token_program: anchor_spl::associated_token::ID,
         system_program: system_program::ID,
         token_program: anchor_spl::token::ID,
@@ -122,7 +145,7 @@ fn test_make_and_refund() {
     let refund_data = instruction::Refund {};
 
     let refund_instruction = Instruction {
-        program_id: program_id,
+        program_id,
         accounts: refund_accounts.to_account_metas(None),
         data: refund_data.data(),
     };
@@ -220,23 +243,25 @@ fn test_make_and_take() {
 
     let make_accounts = accounts::Make {
         maker: maker.pubkey(),
-        mint_a: mint_a,
-        mint_b: mint_b,
-        maker_ata_a: maker_ata_a,
-        escrow: escrow,
-        vault: vault,
+        mint_a,
+        mint_b,
+        maker_ata_a,
+        escrow,
+        order_book: order_book_address(&mint_a, &mint_b),
+        vault,
         associated_token_program: anchor_spl::associated_token::ID,
         system_program: system_program::ID,
         token_program: anchor_spl::token::ID,
     };
     let make_data = instruction::Make {
-        seed: seed,
-        deposit: deposit,
-        receive: receive,
+        seed,
+        deposit,
+        receive,
+        options: MakeOptions::default(),
     };
 
     let make_instruction = Instruction {
-        program_id: program_id,
+        program_id,
         accounts: make_accounts.to_account_metas(None),
         data: make_data.data(),
     };
@@ -257,21 +282,25 @@ fn test_make_and_take() {
     let take_accounts = accounts::Take {
         taker: taker.pubkey(),
         maker: maker.pubkey(),
-        mint_a: mint_a,
-        mint_b: mint_b,
-        taker_ata_a: taker_ata_a,
-        taker_ata_b: taker_ata_b,
-        maker_ata_b: maker_ata_b,
-        escrow: escrow,
-        vault: vault,
+        mint_a,
+        mint_b,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b,
+        escrow,
+        order_book: order_book_address(&mint_a, &mint_b),
+        vault,
         associated_token_program: anchor_spl::associated_token::ID,
         system_program: system_program::ID,
         token_program: anchor_spl::token::ID,
     };
-    let take_data = instruction::Take {};
+    let take_data = instruction::Take {
+        amount: receive,
+        proof: vec![],
+    };
 
     let take_instruction = Instruction {
-        program_id: program_id,
+        program_id,
         accounts: take_accounts.to_account_metas(None),
         data: take_data.data(),
     };
@@ -322,3 +351,1923 @@ fn test_make_and_take() {
 
     println!("\nTake instruction test passed successfully!");
 }
+
+#[test]
+fn test_make_and_partial_take() {
+    // ============================================================================
+    // Test Env Setup: Initialize environment and deploy escrow program
+    // ============================================================================
+    let mut svm = LiteSVM::new();
+
+    let program_keypair = read_keypair_file("target/deploy/anchor_escrow_q4_25-keypair.json").unwrap();
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/anchor_escrow_q4_25.so");
+
+    svm.add_program(program_id, program_bytes);
+
+    // ============================================================================
+    // Create and fund test accounts
+    // ============================================================================
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    svm.airdrop(&maker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+    svm.airdrop(&taker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+
+    // ============================================================================
+    // Token Setup: Create mints and token accounts
+    // ============================================================================
+    let mint_a = CreateMint::new(&mut svm, &maker)
+        .authority(&maker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let mint_b = CreateMint::new(&mut svm, &maker)
+        .authority(&taker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a)
+        .owner(&maker.pubkey())
+        .send()
+        .unwrap();
+    let maker_ata_b = get_associated_token_address(&maker.pubkey(), &mint_b);
+    let taker_ata_a = get_associated_token_address(&taker.pubkey(), &mint_a);
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, 10_000_000)
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &taker, &mint_b, &taker_ata_b, 5_000_000)
+        .send()
+        .unwrap();
+
+    // ============================================================================
+    // Test: Make an escrow that accepts partial fills
+    // ============================================================================
+    let seed: u64 = 42;
+    let (escrow, _bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &program_id,
+    );
+    let vault = get_associated_token_address(&escrow, &mint_a);
+
+    let make_instruction = Instruction {
+        program_id,
+        accounts: accounts::Make {
+            maker: maker.pubkey(),
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            escrow,
+            order_book: order_book_address(&mint_a, &mint_b),
+            vault,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Make {
+            seed,
+            deposit: 10_000_000,
+            receive: 5_000_000,
+            options: MakeOptions {
+                partial_fill: true,
+                ..Default::default()
+            },
+        }
+        .data(),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[make_instruction],
+        Some(&maker.pubkey()),
+        &[&maker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    let take_accounts = accounts::Take {
+        taker: taker.pubkey(),
+        maker: maker.pubkey(),
+        mint_a,
+        mint_b,
+        taker_ata_a,
+        taker_ata_b,
+        maker_ata_b,
+        escrow,
+        order_book: order_book_address(&mint_a, &mint_b),
+        vault,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+    };
+
+    // ============================================================================
+    // Test: Fill 40% of the order, escrow and vault must stay open
+    // ============================================================================
+    let take_instruction = Instruction {
+        program_id,
+        accounts: take_accounts.to_account_metas(None),
+        data: instruction::Take {
+            amount: 2_000_000,
+            proof: vec![],
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[take_instruction],
+        Some(&taker.pubkey()),
+        &[&taker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    let escrow_account = svm.get_account(&escrow).unwrap();
+    let escrow_state =
+        Escrow::try_deserialize(&mut escrow_account.data.as_slice()).unwrap();
+    assert_eq!(escrow_state.remaining_receive, 3_000_000);
+    assert_eq!(escrow_state.remaining_deposit, 6_000_000);
+
+    let vault_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&svm, &vault).unwrap();
+    assert_eq!(vault_state.amount, 6_000_000, "Vault should keep the unfilled share");
+
+    let taker_ata_a_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&svm, &taker_ata_a).unwrap();
+    assert_eq!(taker_ata_a_state.amount, 4_000_000, "Taker should receive a pro-rata share");
+
+    // ============================================================================
+    // Test: Fill the rest, escrow and vault must be closed
+    // ============================================================================
+    let take_instruction = Instruction {
+        program_id,
+        accounts: take_accounts.to_account_metas(None),
+        data: instruction::Take {
+            amount: 3_000_000,
+            proof: vec![],
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[take_instruction],
+        Some(&taker.pubkey()),
+        &[&taker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    let escrow_closed = match svm.get_account(&escrow) {
+        None => true,
+        Some(account) => account.lamports == 0 && account.data.is_empty(),
+    };
+    assert!(escrow_closed, "Escrow account should be closed once fully filled");
+
+    let vault_closed = match svm.get_account(&vault) {
+        None => true,
+        Some(account) => account.lamports == 0 && account.data.is_empty(),
+    };
+    assert!(vault_closed, "Vault account should be closed once fully filled");
+
+    let taker_ata_a_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&svm, &taker_ata_a).unwrap();
+    assert_eq!(taker_ata_a_state.amount, 10_000_000);
+
+    let maker_ata_b_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&svm, &maker_ata_b).unwrap();
+    assert_eq!(maker_ata_b_state.amount, 5_000_000);
+
+    println!("\nPartial take test passed successfully!");
+}
+
+#[test]
+fn test_take_after_expiry_and_refund_expired() {
+    // ============================================================================
+    // Test Env Setup: Initialize environment and deploy escrow program
+    // ============================================================================
+    let mut svm = LiteSVM::new();
+
+    let program_keypair = read_keypair_file("target/deploy/anchor_escrow_q4_25-keypair.json").unwrap();
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/anchor_escrow_q4_25.so");
+
+    svm.add_program(program_id, program_bytes);
+
+    // ============================================================================
+    // Create and fund test accounts, the keeper cranks the refund
+    // ============================================================================
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let keeper = Keypair::new();
+    svm.airdrop(&maker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+    svm.airdrop(&taker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+    svm.airdrop(&keeper.pubkey(), 1_000_000_000).unwrap(); // 1 SOL
+
+    // ============================================================================
+    // Token Setup: Create mints and token accounts
+    // ============================================================================
+    let mint_a = CreateMint::new(&mut svm, &maker)
+        .authority(&maker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let mint_b = CreateMint::new(&mut svm, &maker)
+        .authority(&taker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a)
+        .owner(&maker.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, 10_000_000)
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &taker, &mint_b, &taker_ata_b, 5_000_000)
+        .send()
+        .unwrap();
+
+    // ============================================================================
+    // Test: Make an escrow expiring in one hour
+    // ============================================================================
+    let mut clock = svm.get_sysvar::<Clock>();
+    let expires_at = clock.unix_timestamp + 3_600;
+
+    let seed: u64 = 7;
+    let (escrow, _bump) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &program_id,
+    );
+    let vault = get_associated_token_address(&escrow, &mint_a);
+
+    let make_instruction = Instruction {
+        program_id,
+        accounts: accounts::Make {
+            maker: maker.pubkey(),
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            escrow,
+            order_book: order_book_address(&mint_a, &mint_b),
+            vault,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Make {
+            seed,
+            deposit: 10_000_000,
+            receive: 5_000_000,
+            options: MakeOptions {
+                expires_at: Some(expires_at),
+                ..Default::default()
+            },
+        }
+        .data(),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[make_instruction],
+        Some(&maker.pubkey()),
+        &[&maker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    let refund_expired_instruction = Instruction {
+        program_id,
+        accounts: accounts::RefundExpired {
+            payer: keeper.pubkey(),
+            maker: maker.pubkey(),
+            mint_a,
+            maker_ata_a,
+            escrow,
+            order_book: order_book_address(&mint_a, &mint_b),
+            vault,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RefundExpired {}.data(),
+    };
+
+    // ============================================================================
+    // Test: The crank is rejected before expiry
+    // ============================================================================
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&refund_expired_instruction),
+        Some(&keeper.pubkey()),
+        &[&keeper],
+        svm.latest_blockhash(),
+    );
+    let err = svm.send_transaction(tx).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(EscrowError::EscrowNotExpired.into()))
+    );
+
+    // ============================================================================
+    // Test: Take is rejected once the escrow has expired
+    // ============================================================================
+    clock.unix_timestamp = expires_at;
+    svm.set_sysvar::<Clock>(&clock);
+    svm.expire_blockhash();
+
+    let take_instruction = Instruction {
+        program_id,
+        accounts: accounts::Take {
+            taker: taker.pubkey(),
+            maker: maker.pubkey(),
+            mint_a,
+            mint_b,
+            taker_ata_a: get_associated_token_address(&taker.pubkey(), &mint_a),
+            taker_ata_b,
+            maker_ata_b: get_associated_token_address(&maker.pubkey(), &mint_b),
+            escrow,
+            order_book: order_book_address(&mint_a, &mint_b),
+            vault,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Take {
+            amount: 5_000_000,
+            proof: vec![],
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[take_instruction],
+        Some(&taker.pubkey()),
+        &[&taker],
+        svm.latest_blockhash(),
+    );
+    let err = svm.send_transaction(tx).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(EscrowError::EscrowExpired.into()))
+    );
+
+    // ============================================================================
+    // Test: The keeper refunds the maker after expiry
+    // ============================================================================
+    let maker_lamports_before = svm.get_balance(&maker.pubkey()).unwrap();
+    let locked_rent = svm.get_balance(&escrow).unwrap() + svm.get_balance(&vault).unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[refund_expired_instruction],
+        Some(&keeper.pubkey()),
+        &[&keeper],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    let escrow_closed = match svm.get_account(&escrow) {
+        None => true,
+        Some(account) => account.lamports == 0 && account.data.is_empty(),
+    };
+    assert!(escrow_closed, "Escrow account should be closed");
+
+    let vault_closed = match svm.get_account(&vault) {
+        None => true,
+        Some(account) => account.lamports == 0 && account.data.is_empty(),
+    };
+    assert!(vault_closed, "Vault account should be closed");
+
+    let maker_ata_a_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&svm, &maker_ata_a).unwrap();
+    assert_eq!(maker_ata_a_state.amount, 10_000_000, "Maker should get the deposit back");
+
+    let maker_lamports_after = svm.get_balance(&maker.pubkey()).unwrap();
+    assert_eq!(maker_lamports_after, maker_lamports_before + locked_rent, "Rent should go back to the maker");
+
+    println!("\nExpired escrow refunded by keeper successfully!");
+}
+
+// ============================================================================
+// Shared setup for the tests below
+// ============================================================================
+
+struct TestEnv {
+    svm: LiteSVM,
+    program_id: Pubkey,
+    maker: Keypair,
+    taker: Keypair,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    maker_ata_a: Pubkey,
+    token_program: Pubkey,
+}
+
+/// Deploys the program and funds a maker with 10 mint_a tokens and a taker with
+/// 5 mint_b tokens.
+fn setup() -> TestEnv {
+    let mut svm = LiteSVM::new();
+
+    let program_keypair = read_keypair_file("target/deploy/anchor_escrow_q4_25-keypair.json").unwrap();
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/anchor_escrow_q4_25.so");
+
+    svm.add_program(program_id, program_bytes);
+
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    svm.airdrop(&maker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+    svm.airdrop(&taker.pubkey(), 10_000_000_000).unwrap(); // 10 SOL
+
+    let mint_a = CreateMint::new(&mut svm, &maker)
+        .authority(&maker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let mint_b = CreateMint::new(&mut svm, &maker)
+        .authority(&taker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a)
+        .owner(&maker.pubkey())
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, 10_000_000)
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &taker, &mint_b, &taker_ata_b, 5_000_000)
+        .send()
+        .unwrap();
+
+    TestEnv {
+        svm,
+        program_id,
+        maker,
+        taker,
+        mint_a,
+        mint_b,
+        maker_ata_a,
+        token_program: anchor_spl::token::ID,
+    }
+}
+
+fn escrow_address(env: &TestEnv, seed: u64) -> (Pubkey, Pubkey) {
+    let escrow = anchor_escrow_client::escrow_address(&env.maker.pubkey(), seed);
+    (escrow, vault_address(&escrow, &env.mint_a, &env.token_program))
+}
+
+fn ata(env: &TestEnv, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &env.token_program)
+}
+
+fn order_book(env: &TestEnv, mint_b: &Pubkey) -> OrderBook {
+    let address = order_book_address(&env.mint_a, mint_b);
+    let account = env.svm.get_account(&address).unwrap();
+    OrderBook::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn make_instruction(env: &TestEnv, seed: u64, options: MakeOptions) -> Instruction {
+    make_ix(
+        &env.maker.pubkey(),
+        &env.mint_a,
+        &env.mint_b,
+        &env.token_program,
+        instruction::Make {
+            seed,
+            deposit: 10_000_000,
+            receive: 5_000_000,
+            options,
+        },
+    )
+}
+
+fn take_instruction(
+    env: &TestEnv,
+    taker: &Pubkey,
+    seed: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (escrow, _) = escrow_address(env, seed);
+    let escrow_state = decode_escrow(&env.svm.get_account(&escrow).unwrap().data).unwrap();
+
+    take_ix(
+        taker,
+        &escrow_state,
+        &env.token_program,
+        instruction::Take { amount, proof },
+    )
+}
+
+fn send(
+    svm: &mut LiteSVM,
+    instruction: Instruction,
+    signer: &Keypair,
+) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).map(|_| ()).map_err(|failed| failed.err)
+}
+
+fn assert_escrow_error(result: Result<(), TransactionError>, expected: EscrowError) {
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into()))
+    );
+}
+
+/// Same as `assert_escrow_error` for errors raised by Anchor's account validation.
+fn assert_anchor_error(result: Result<(), TransactionError>, expected: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into()))
+    );
+}
+
+/// Funds `taker` with mint_b so it can take an escrow made in `env`.
+fn fund_taker(env: &mut TestEnv, taker: &Keypair) {
+    env.svm.airdrop(&taker.pubkey(), 1_000_000_000).unwrap(); // 1 SOL
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut env.svm, taker, &env.mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut env.svm, &env.taker, &env.mint_b, &taker_ata_b, 5_000_000)
+        .send()
+        .unwrap();
+}
+
+#[test]
+fn test_take_with_designated_taker() {
+    let mut env = setup();
+    let outsider = Keypair::new();
+    fund_taker(&mut env, &outsider);
+
+    let seed: u64 = 11;
+    let make_ix = make_instruction(
+        &env,
+        seed,
+        MakeOptions {
+            allowed_taker: Some(env.taker.pubkey()),
+            ..Default::default()
+        },
+    );
+    let maker = env.maker.insecure_clone();
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    // Anyone else is rejected
+    let take_ix = take_instruction(&env, &outsider.pubkey(), seed, 5_000_000, vec![]);
+    assert_escrow_error(send(&mut env.svm, take_ix, &outsider), EscrowError::TakerNotAllowed);
+
+    // The designated counterparty can take
+    let taker = env.taker.insecure_clone();
+    let take_ix = take_instruction(&env, &taker.pubkey(), seed, 5_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    let taker_ata_a = get_associated_token_address(&taker.pubkey(), &env.mint_a);
+    let taker_ata_a_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&env.svm, &taker_ata_a).unwrap();
+    assert_eq!(taker_ata_a_state.amount, 10_000_000);
+}
+
+#[test]
+fn test_take_with_merkle_allowlist() {
+    let mut env = setup();
+    let outsider = Keypair::new();
+    fund_taker(&mut env, &outsider);
+
+    // Whitelist of four takers, env.taker sits at index 2
+    let whitelist = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        env.taker.pubkey(),
+        Pubkey::new_unique(),
+    ];
+    let leaves: Vec<[u8; 32]> = whitelist.iter().map(merkle::leaf).collect();
+    let left = merkle::node(&leaves[0], &leaves[1]);
+    let right = merkle::node(&leaves[2], &leaves[3]);
+    let root = merkle::node(&left, &right);
+    let proof = vec![leaves[3], left];
+
+    let seed: u64 = 12;
+    let make_ix = make_instruction(
+        &env,
+        seed,
+        MakeOptions {
+            allowed_takers_root: Some(root),
+            ..Default::default()
+        },
+    );
+    let maker = env.maker.insecure_clone();
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    // A taker outside the tree cannot reuse someone else's proof
+    let take_ix = take_instruction(&env, &outsider.pubkey(), seed, 5_000_000, proof.clone());
+    assert_escrow_error(send(&mut env.svm, take_ix, &outsider), EscrowError::TakerNotAllowed);
+
+    // A whitelisted taker without a proof is rejected as well
+    let taker = env.taker.insecure_clone();
+    let take_ix = take_instruction(&env, &taker.pubkey(), seed, 5_000_000, vec![]);
+    assert_escrow_error(send(&mut env.svm, take_ix, &taker), EscrowError::TakerNotAllowed);
+
+    let take_ix = take_instruction(&env, &taker.pubkey(), seed, 5_000_000, proof);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    let taker_ata_a = get_associated_token_address(&taker.pubkey(), &env.mint_a);
+    let taker_ata_a_state = litesvm_token::get_spl_account::<spl_token::state::Account>(&env.svm, &taker_ata_a).unwrap();
+    assert_eq!(taker_ata_a_state.amount, 10_000_000);
+}
+
+/// Creates `count` mints owned by `authority`, each with an ATA holding 1_000_000
+/// tokens for `authority`.
+fn create_funded_mints(svm: &mut LiteSVM, authority: &Keypair, count: usize) -> Vec<(Pubkey, Pubkey)> {
+    (0..count)
+        .map(|_| {
+            let mint = CreateMint::new(svm, authority)
+                .authority(&authority.pubkey())
+                .decimals(DECIMALS)
+                .send()
+                .unwrap();
+            let ata = CreateAssociatedTokenAccount::new(svm, authority, &mint)
+                .owner(&authority.pubkey())
+                .send()
+                .unwrap();
+            MintTo::new(svm, authority, &mint, &ata, 1_000_000).send().unwrap();
+            (mint, ata)
+        })
+        .collect()
+}
+
+/// Works for both token programs, including Token-2022 accounts with extensions.
+fn token_balance(svm: &LiteSVM, token_account: &Pubkey) -> u64 {
+    let account = svm.get_account(token_account).unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+fn is_closed(svm: &LiteSVM, address: &Pubkey) -> bool {
+    match svm.get_account(address) {
+        None => true,
+        Some(account) => account.lamports == 0 && account.data.is_empty(),
+    }
+}
+
+fn make_bundle_instruction(
+    env: &TestEnv,
+    escrow: Pubkey,
+    seed: u64,
+    offered: &[(Pubkey, Pubkey)],
+    requested: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut account_metas = accounts::MakeBundle {
+        maker: env.maker.pubkey(),
+        escrow,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+
+    for (mint, maker_ata) in offered {
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new(*maker_ata, false));
+        account_metas.push(AccountMeta::new(get_associated_token_address(&escrow, mint), false));
+    }
+    for (mint, _) in requested {
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+    }
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: account_metas,
+        data: instruction::MakeBundle {
+            seed,
+            offered: offered
+                .iter()
+                .map(|(mint, _)| BundleLeg { mint: *mint, amount: 1_000_000 })
+                .collect(),
+            requested: requested
+                .iter()
+                .map(|(mint, _)| BundleLeg { mint: *mint, amount: 1_000_000 })
+                .collect(),
+        }
+        .data(),
+    }
+}
+
+#[test]
+fn test_make_and_take_bundle() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // ============================================================================
+    // Bundle Setup: maker offers 3 mints, wants 2 mints in return
+    // ============================================================================
+    let offered = create_funded_mints(&mut env.svm, &maker, 3);
+    let requested = create_funded_mints(&mut env.svm, &taker, 2);
+
+    let seed: u64 = 30;
+    let (escrow, _bump) = Pubkey::find_program_address(
+        &[b"bundle", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &env.program_id,
+    );
+
+    let make_ix = make_bundle_instruction(&env, escrow, seed, &offered, &requested);
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    for (mint, maker_ata) in &offered {
+        assert_eq!(token_balance(&env.svm, maker_ata), 0);
+        assert_eq!(token_balance(&env.svm, &get_associated_token_address(&escrow, mint)), 1_000_000);
+    }
+
+    // The taker and maker need ATAs for the mints they receive
+    for (mint, _) in &offered {
+        CreateAssociatedTokenAccount::new(&mut env.svm, &taker, mint)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+    }
+    for (mint, _) in &requested {
+        CreateAssociatedTokenAccount::new(&mut env.svm, &maker, mint)
+            .owner(&maker.pubkey())
+            .send()
+            .unwrap();
+    }
+
+    // ============================================================================
+    // Test: take the whole bundle atomically
+    // ============================================================================
+    let mut account_metas = accounts::TakeBundle {
+        taker: taker.pubkey(),
+        maker: maker.pubkey(),
+        escrow,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    for (mint, _) in &offered {
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new(get_associated_token_address(&escrow, mint), false));
+        account_metas.push(AccountMeta::new(get_associated_token_address(&taker.pubkey(), mint), false));
+    }
+    for (mint, taker_ata) in &requested {
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new(*taker_ata, false));
+        account_metas.push(AccountMeta::new(get_associated_token_address(&maker.pubkey(), mint), false));
+    }
+
+    // Leaving out the last requested leg must fail
+    let short_ix = Instruction {
+        program_id: env.program_id,
+        accounts: account_metas[..account_metas.len() - 3].to_vec(),
+        data: instruction::TakeBundle {}.data(),
+    };
+    assert_escrow_error(send(&mut env.svm, short_ix, &taker), EscrowError::InvalidBundleAccount);
+
+    let take_ix = Instruction {
+        program_id: env.program_id,
+        accounts: account_metas,
+        data: instruction::TakeBundle {}.data(),
+    };
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    for (mint, _) in &offered {
+        assert_eq!(token_balance(&env.svm, &get_associated_token_address(&taker.pubkey(), mint)), 1_000_000);
+        assert!(is_closed(&env.svm, &get_associated_token_address(&escrow, mint)));
+    }
+    for (mint, taker_ata) in &requested {
+        assert_eq!(token_balance(&env.svm, taker_ata), 0);
+        assert_eq!(token_balance(&env.svm, &get_associated_token_address(&maker.pubkey(), mint)), 1_000_000);
+    }
+    assert!(is_closed(&env.svm, &escrow), "Bundle escrow should be closed");
+}
+
+#[test]
+fn test_make_and_refund_bundle() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let offered = create_funded_mints(&mut env.svm, &maker, 2);
+    let requested = create_funded_mints(&mut env.svm, &taker, 1);
+
+    let seed: u64 = 31;
+    let (escrow, _bump) = Pubkey::find_program_address(
+        &[b"bundle", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &env.program_id,
+    );
+
+    let make_ix = make_bundle_instruction(&env, escrow, seed, &offered, &requested);
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let mut account_metas = accounts::RefundBundle {
+        maker: maker.pubkey(),
+        escrow,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+    }
+    .to_account_metas(None);
+    for (mint, maker_ata) in &offered {
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new(get_associated_token_address(&escrow, mint), false));
+        account_metas.push(AccountMeta::new(*maker_ata, false));
+    }
+
+    let refund_ix = Instruction {
+        program_id: env.program_id,
+        accounts: account_metas,
+        data: instruction::RefundBundle {}.data(),
+    };
+    send(&mut env.svm, refund_ix, &maker).unwrap();
+
+    for (mint, maker_ata) in &offered {
+        assert_eq!(token_balance(&env.svm, maker_ata), 1_000_000);
+        assert!(is_closed(&env.svm, &get_associated_token_address(&escrow, mint)));
+    }
+    assert!(is_closed(&env.svm, &escrow), "Bundle escrow should be closed");
+}
+
+#[test]
+fn test_make_bundle_with_precreated_vault() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let offered = create_funded_mints(&mut env.svm, &maker, 2);
+    let requested = create_funded_mints(&mut env.svm, &taker, 1);
+
+    let seed: u64 = 32;
+    let (escrow, _bump) = Pubkey::find_program_address(
+        &[b"bundle", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &env.program_id,
+    );
+
+    // Anyone can create a vault ATA ahead of the maker; that must not block the bundle
+    CreateAssociatedTokenAccount::new(&mut env.svm, &taker, &offered[0].0)
+        .owner(&escrow)
+        .send()
+        .unwrap();
+
+    let make_ix = make_bundle_instruction(&env, escrow, seed, &offered, &requested);
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    for (mint, _) in &offered {
+        assert_eq!(token_balance(&env.svm, &get_associated_token_address(&escrow, mint)), 1_000_000);
+    }
+}
+
+fn update_escrow_instruction(
+    env: &TestEnv,
+    seed: u64,
+    new_mint_b: Option<Pubkey>,
+    receive: Option<u64>,
+    deposit_change: Option<DepositChange>,
+) -> Instruction {
+    let (escrow, vault) = escrow_address(env, seed);
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: accounts::UpdateEscrow {
+            maker: env.maker.pubkey(),
+            mint_a: env.mint_a,
+            new_mint_b,
+            maker_ata_a: env.maker_ata_a,
+            escrow,
+            order_book: order_book_address(&env.mint_a, &env.mint_b),
+            new_order_book: new_mint_b
+                .map(|mint_b| order_book_address(&env.mint_a, &mint_b)),
+            vault,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateEscrow {
+            receive,
+            deposit_change,
+        }
+        .data(),
+    }
+}
+
+#[test]
+fn test_update_escrow_reprices_and_changes_mint_b() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let seed: u64 = 40;
+    let (escrow, vault) = escrow_address(&env, seed);
+    let make_ix = make_instruction(&env, seed, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    // Maker now wants a different token, fewer of them, and offers less
+    let new_mint_b = CreateMint::new(&mut env.svm, &maker)
+        .authority(&taker.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let taker_ata_new_b = CreateAssociatedTokenAccount::new(&mut env.svm, &taker, &new_mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut env.svm, &taker, &new_mint_b, &taker_ata_new_b, 4_000_000)
+        .send()
+        .unwrap();
+
+    // Nobody has listed on the new pair yet, so its order book is created first
+    let init_book_ix = Instruction {
+        program_id: env.program_id,
+        accounts: accounts::InitOrderBook {
+            payer: maker.pubkey(),
+            mint_a: env.mint_a,
+            mint_b: new_mint_b,
+            order_book: order_book_address(&env.mint_a, &new_mint_b),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitOrderBook {}.data(),
+    };
+    send(&mut env.svm, init_book_ix, &maker).unwrap();
+
+    let update_ix = update_escrow_instruction(
+        &env,
+        seed,
+        Some(new_mint_b),
+        Some(4_000_000),
+        Some(DepositChange::Withdraw(2_000_000)),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&maker.pubkey()),
+        &[&maker],
+        env.svm.latest_blockhash(),
+    );
+    let meta = env.svm.send_transaction(tx).unwrap();
+    assert!(
+        meta.logs.iter().any(|log| log.starts_with("Program data: ")),
+        "EscrowUpdated event should be emitted"
+    );
+
+    let escrow_account = env.svm.get_account(&escrow).unwrap();
+    let escrow_state = decode_escrow(&escrow_account.data).unwrap();
+    assert_eq!(escrow_state.mint_b, new_mint_b);
+    assert_eq!(escrow_state.receive, 4_000_000);
+    assert_eq!(escrow_state.remaining_deposit, 8_000_000);
+    assert_eq!(token_balance(&env.svm, &vault), 8_000_000);
+    assert_eq!(token_balance(&env.svm, &env.maker_ata_a), 2_000_000);
+
+    // The listing moved to the new pair's order book
+    assert!(order_book(&env, &env.mint_b).orders.is_empty());
+    assert_eq!(
+        order_book(&env, &new_mint_b).orders,
+        vec![Order::new(escrow, 8_000_000, 4_000_000).unwrap()]
+    );
+
+    // Takers must now pay in the new mint
+    env.mint_b = new_mint_b;
+    let take_ix = take_instruction(&env, &taker.pubkey(), seed, 4_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    let taker_ata_a = get_associated_token_address(&taker.pubkey(), &env.mint_a);
+    assert_eq!(token_balance(&env.svm, &taker_ata_a), 8_000_000);
+    assert_eq!(
+        token_balance(&env.svm, &get_associated_token_address(&maker.pubkey(), &new_mint_b)),
+        4_000_000
+    );
+    assert!(is_closed(&env.svm, &escrow));
+}
+
+#[test]
+fn test_update_escrow_rejected_after_partial_take() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let seed: u64 = 41;
+    let make_ix = make_instruction(
+        &env,
+        seed,
+        MakeOptions {
+            partial_fill: true,
+            ..Default::default()
+        },
+    );
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let take_ix = take_instruction(&env, &taker.pubkey(), seed, 1_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    let update_ix = update_escrow_instruction(
+        &env,
+        seed,
+        None,
+        Some(1_000_000),
+        Some(DepositChange::TopUp(0)),
+    );
+    assert_escrow_error(send(&mut env.svm, update_ix, &maker), EscrowError::TakeInProgress);
+}
+
+#[test]
+fn test_order_book_tracks_open_escrows() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    // Two escrows of 10 tokens, both asking 5 tokens to start with
+    MintTo::new(&mut env.svm, &maker, &env.mint_a, &env.maker_ata_a, 10_000_000)
+        .send()
+        .unwrap();
+    let options = MakeOptions {
+        partial_fill: true,
+        ..MakeOptions::default()
+    };
+    let (cheap_escrow, _) = escrow_address(&env, 60);
+    let (dear_escrow, _) = escrow_address(&env, 61);
+    for seed in [60, 61] {
+        let make_ix = make_instruction(&env, seed, options.clone());
+        send(&mut env.svm, make_ix, &maker).unwrap();
+    }
+
+    let update_ix = update_escrow_instruction(&env, 61, None, Some(6_000_000), None);
+    send(&mut env.svm, update_ix, &maker).unwrap();
+
+    let book = order_book(&env, &env.mint_b);
+    assert_eq!(book.mint_a, env.mint_a);
+    assert_eq!(book.mint_b, env.mint_b);
+    assert_eq!(
+        book.best_offers(2),
+        vec![
+            Order::new(cheap_escrow, 10_000_000, 5_000_000).unwrap(),
+            Order::new(dear_escrow, 10_000_000, 6_000_000).unwrap(),
+        ]
+    );
+    assert_eq!(book.best_offers(2)[0].price, 500_000_000);
+
+    // A partial fill keeps the listing with what is left
+    let take_ix = take_instruction(&env, &taker.pubkey(), 60, 1_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+    assert_eq!(
+        order_book(&env, &env.mint_b).best_offers(1),
+        vec![Order::new(cheap_escrow, 8_000_000, 4_000_000).unwrap()]
+    );
+
+    // Refunding and fully filling both remove their listing
+    let dear_escrow_state =
+        decode_escrow(&env.svm.get_account(&dear_escrow).unwrap().data).unwrap();
+    let refund_ix = refund_ix(&dear_escrow_state, &env.token_program);
+    send(&mut env.svm, refund_ix, &maker).unwrap();
+    assert_eq!(order_book(&env, &env.mint_b).orders.len(), 1);
+
+    let take_ix = take_instruction(&env, &taker.pubkey(), 60, 4_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+    assert!(order_book(&env, &env.mint_b).orders.is_empty());
+}
+
+#[test]
+fn test_make_succeeds_with_full_order_book_and_extreme_price() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    MintTo::new(&mut env.svm, &maker, &env.mint_a, &env.maker_ata_a, 1_000)
+        .send()
+        .unwrap();
+
+    // Fill the book with dust escrows asking 100 SOL worth of mint_b per base unit,
+    // a price ratio that doesn't fit a u64
+    let dust = |seed: u64| {
+        make_ix(
+            &maker.pubkey(),
+            &env.mint_a,
+            &env.mint_b,
+            &env.token_program,
+            instruction::Make {
+                seed,
+                deposit: 1,
+                receive: 100_000_000_000,
+                options: MakeOptions::default(),
+            },
+        )
+    };
+    for seed in 0..MAX_ORDERS as u64 {
+        let make_ix = dust(100 + seed);
+        send(&mut env.svm, make_ix, &maker).unwrap();
+    }
+    assert_eq!(order_book(&env, &env.mint_b).orders.len(), MAX_ORDERS);
+    assert_eq!(
+        order_book(&env, &env.mint_b).best_offers(1)[0].price,
+        100_000_000_000 * PRICE_SCALE
+    );
+
+    // A worse offer is still made, just not listed
+    let make_ix = dust(200);
+    send(&mut env.svm, make_ix, &maker).unwrap();
+    let (unlisted, _) = escrow_address(&env, 200);
+    assert!(order_book(&env, &env.mint_b).orders.iter().all(|o| o.escrow != unlisted));
+
+    // A cheaper offer evicts one of the dust listings
+    let make_ix = make_instruction(&env, 201, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+    let (cheap, _) = escrow_address(&env, 201);
+    let book = order_book(&env, &env.mint_b);
+    assert_eq!(book.orders.len(), MAX_ORDERS);
+    assert_eq!(book.best_offers(1)[0].escrow, cheap);
+}
+
+// ============================================================================
+// mpl-core NFT escrows
+// ============================================================================
+
+const CORE_PROGRAM_FIXTURE: &str = "tests/fixtures/mpl_core.so";
+
+/// Loads the mpl-core program dumped from mainnet, see the README. The dump isn't
+/// committed, so the NFT tests are ignored unless run with `--ignored`.
+fn add_core_program(svm: &mut LiteSVM) {
+    svm.add_program_from_file(mpl_core::ID, CORE_PROGRAM_FIXTURE)
+        .expect("failed to load tests/fixtures/mpl_core.so, dump it as described in the README");
+}
+
+fn create_asset(svm: &mut LiteSVM, owner: &Keypair) -> Pubkey {
+    let asset = Keypair::new();
+    let create_instruction = CreateV2Builder::new()
+        .asset(asset.pubkey())
+        .payer(owner.pubkey())
+        .name("Escrowed".to_string())
+        .uri("https://example.com/asset.json".to_string())
+        .instruction();
+    let tx = Transaction::new_signed_with_payer(
+        &[create_instruction],
+        Some(&owner.pubkey()),
+        &[owner, &asset],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    asset.pubkey()
+}
+
+fn asset_state(svm: &LiteSVM, asset: &Pubkey) -> BaseAssetV1 {
+    BaseAssetV1::from_bytes(&svm.get_account(asset).unwrap().data).unwrap()
+}
+
+fn nft_escrow_address(env: &TestEnv, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"nft_escrow", env.maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &env.program_id,
+    )
+    .0
+}
+
+fn make_nft_instruction(
+    env: &TestEnv,
+    seed: u64,
+    asset: Pubkey,
+    custody: NftCustody,
+    payment: NftPayment,
+) -> Instruction {
+    Instruction {
+        program_id: env.program_id,
+        accounts: accounts::MakeNft {
+            maker: env.maker.pubkey(),
+            asset,
+            collection: None,
+            escrow: nft_escrow_address(env, seed),
+            core_program: mpl_core::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MakeNft { seed, custody, payment }.data(),
+    }
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/mpl_core.so"]
+fn test_make_nft_in_custody_and_take_with_tokens() {
+    let mut env = setup();
+    add_core_program(&mut env.svm);
+
+    let asset = create_asset(&mut env.svm, &env.maker);
+    let escrow = nft_escrow_address(&env, 1);
+    let payment = NftPayment::Token { mint: env.mint_b, amount: 5_000_000 };
+
+    let ix = make_nft_instruction(&env, 1, asset, NftCustody::Transfer, payment);
+    send(&mut env.svm, ix, &env.maker).unwrap();
+    assert_eq!(asset_state(&env.svm, &asset).owner, escrow, "Escrow should hold the asset");
+
+    let taker_ata_b = get_associated_token_address(&env.taker.pubkey(), &env.mint_b);
+    let maker_ata_b = CreateAssociatedTokenAccount::new(&mut env.svm, &env.maker, &env.mint_b)
+        .owner(&env.maker.pubkey())
+        .send()
+        .unwrap();
+
+    let take_instruction = Instruction {
+        program_id: env.program_id,
+        accounts: accounts::TakeNft {
+            taker: env.taker.pubkey(),
+            maker: env.maker.pubkey(),
+            asset,
+            collection: None,
+            escrow,
+            payment_mint: Some(env.mint_b),
+            taker_payment_ata: Some(taker_ata_b),
+            maker_payment_ata: Some(maker_ata_b),
+            payment_asset: None,
+            payment_collection: None,
+            core_program: mpl_core::ID,
+            associated_token_program: Some(anchor_spl::associated_token::ID),
+            token_program: Some(anchor_spl::token::ID),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::TakeNft {}.data(),
+    };
+    send(&mut env.svm, take_instruction, &env.taker).unwrap();
+
+    assert_eq!(asset_state(&env.svm, &asset).owner, env.taker.pubkey());
+    assert_eq!(token_balance(&env.svm, &maker_ata_b), 5_000_000);
+    assert!(is_closed(&env.svm, &escrow), "Escrow should be closed");
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/mpl_core.so"]
+fn test_make_nft_frozen_and_swap_for_asset() {
+    let mut env = setup();
+    add_core_program(&mut env.svm);
+
+    let asset = create_asset(&mut env.svm, &env.maker);
+    let taker = Keypair::new();
+    env.svm.airdrop(&taker.pubkey(), 1_000_000_000).unwrap(); // 1 SOL
+    let payment_asset = create_asset(&mut env.svm, &taker);
+    let escrow = nft_escrow_address(&env, 2);
+
+    let ix = make_nft_instruction(
+        &env,
+        2,
+        asset,
+        NftCustody::Freeze,
+        NftPayment::Asset { asset: payment_asset },
+    );
+    send(&mut env.svm, ix, &env.maker).unwrap();
+    assert_eq!(
+        asset_state(&env.svm, &asset).owner,
+        env.maker.pubkey(),
+        "A frozen asset stays with the maker"
+    );
+
+    let take_instruction = Instruction {
+        program_id: env.program_id,
+        accounts: accounts::TakeNft {
+            taker: taker.pubkey(),
+            maker: env.maker.pubkey(),
+            asset,
+            collection: None,
+            escrow,
+            payment_mint: None,
+            taker_payment_ata: None,
+            maker_payment_ata: None,
+            payment_asset: Some(payment_asset),
+            payment_collection: None,
+            core_program: mpl_core::ID,
+            associated_token_program: None,
+            token_program: None,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::TakeNft {}.data(),
+    };
+    send(&mut env.svm, take_instruction, &taker).unwrap();
+
+    assert_eq!(asset_state(&env.svm, &asset).owner, taker.pubkey());
+    assert_eq!(asset_state(&env.svm, &payment_asset).owner, env.maker.pubkey());
+    assert!(is_closed(&env.svm, &escrow), "Escrow should be closed");
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/mpl_core.so"]
+fn test_make_nft_frozen_and_refund() {
+    let mut env = setup();
+    add_core_program(&mut env.svm);
+
+    let asset = create_asset(&mut env.svm, &env.maker);
+    let escrow = nft_escrow_address(&env, 3);
+    let payment = NftPayment::Token { mint: env.mint_b, amount: 5_000_000 };
+
+    let ix = make_nft_instruction(&env, 3, asset, NftCustody::Freeze, payment);
+    send(&mut env.svm, ix, &env.maker).unwrap();
+
+    let refund_instruction = Instruction {
+        program_id: env.program_id,
+        accounts: accounts::RefundNft {
+            maker: env.maker.pubkey(),
+            asset,
+            collection: None,
+            escrow,
+            core_program: mpl_core::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RefundNft {}.data(),
+    };
+    send(&mut env.svm, refund_instruction, &env.maker).unwrap();
+
+    assert!(is_closed(&env.svm, &escrow), "Escrow should be closed");
+
+    // The asset is thawed, so the maker can move it again
+    let recipient = Pubkey::new_unique();
+    let transfer_instruction = TransferV1Builder::new()
+        .asset(asset)
+        .payer(env.maker.pubkey())
+        .new_owner(recipient)
+        .instruction();
+    send(&mut env.svm, transfer_instruction, &env.maker).unwrap();
+    assert_eq!(asset_state(&env.svm, &asset).owner, recipient);
+}
+
+// ============================================================================
+// Token-2022 transfer-fee and transfer-hook mints
+// ============================================================================
+
+/// Transfer fee of the Token-2022 test mints.
+const FEE_BPS: u16 = 100;
+
+/// Creates a Token-2022 mint with an optional transfer fee and transfer hook.
+fn create_mint_2022(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    transfer_fee_bps: Option<u16>,
+    transfer_hook_program: Option<Pubkey>,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let token_program = spl_token_2022::ID;
+
+    let mut extensions = vec![];
+    let mut instructions = vec![];
+    if let Some(bps) = transfer_fee_bps {
+        extensions.push(ExtensionType::TransferFeeConfig);
+        instructions.push(
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &token_program,
+                &mint.pubkey(),
+                Some(&authority.pubkey()),
+                Some(&authority.pubkey()),
+                bps,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    if let Some(hook_program) = transfer_hook_program {
+        extensions.push(ExtensionType::TransferHook);
+        instructions.push(
+            transfer_hook::instruction::initialize(
+                &token_program,
+                &mint.pubkey(),
+                Some(authority.pubkey()),
+                Some(hook_program),
+            )
+            .unwrap(),
+        );
+    }
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint2(
+            &token_program,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            None,
+            DECIMALS,
+        )
+        .unwrap(),
+    );
+
+    let space =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)
+            .unwrap();
+    let create_account = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &mint.pubkey(),
+        svm.minimum_balance_for_rent_exemption(space),
+        space as u64,
+        &token_program,
+    );
+    instructions.insert(0, create_account);
+
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&authority.pubkey()),
+        &[authority, &mint],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    mint.pubkey()
+}
+
+/// Transfer fees harvested to a Token-2022 mint.
+fn withheld_fees(svm: &LiteSVM, mint: &Pubkey) -> u64 {
+    let account = svm.get_account(mint).unwrap();
+    let mint_state =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).unwrap();
+    mint_state
+        .get_extension::<TransferFeeConfig>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+/// Same as `setup`, but mint_a and mint_b are Token-2022 mints. The maker holds 20
+/// mint_a tokens and the taker 10 mint_b tokens.
+fn setup_token_2022(
+    transfer_fee_bps: Option<u16>,
+    mint_a_transfer_hook: Option<Pubkey>,
+) -> TestEnv {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let token_program = spl_token_2022::ID;
+
+    env.token_program = token_program;
+    env.mint_a = create_mint_2022(&mut env.svm, &maker, transfer_fee_bps, mint_a_transfer_hook);
+    env.mint_b = create_mint_2022(&mut env.svm, &taker, transfer_fee_bps, None);
+
+    env.maker_ata_a = CreateAssociatedTokenAccount::new(&mut env.svm, &maker, &env.mint_a)
+        .owner(&maker.pubkey())
+        .token_program_id(&token_program)
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut env.svm, &taker, &env.mint_b)
+        .owner(&taker.pubkey())
+        .token_program_id(&token_program)
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut env.svm, &maker, &env.mint_a, &env.maker_ata_a, 20_000_000)
+        .token_program_id(&token_program)
+        .send()
+        .unwrap();
+    MintTo::new(&mut env.svm, &taker, &env.mint_b, &taker_ata_b, 10_000_000)
+        .token_program_id(&token_program)
+        .send()
+        .unwrap();
+
+    env
+}
+
+#[test]
+fn test_take_with_transfer_fee_and_receive_net() {
+    let mut env = setup_token_2022(Some(FEE_BPS), None);
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let (escrow, vault) = escrow_address(&env, 70);
+    let make_ix = make_instruction(
+        &env,
+        70,
+        MakeOptions {
+            receive_net: true,
+            ..MakeOptions::default()
+        },
+    );
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    // The escrow records what reached the vault after the 1% fee on mint_a
+    let escrow_account = env.svm.get_account(&escrow).unwrap();
+    let escrow_state = decode_escrow(&escrow_account.data).unwrap();
+    assert_eq!(token_balance(&env.svm, &vault), 9_900_000);
+    assert_eq!(escrow_state.remaining_deposit, 9_900_000);
+
+    let take_ix = take_instruction(&env, &taker.pubkey(), 70, 5_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    // The taker pays the fee on top, so the maker receives exactly `receive`
+    let maker_ata_b = ata(&env, &maker.pubkey(), &env.mint_b);
+    let taker_ata_b = ata(&env, &taker.pubkey(), &env.mint_b);
+    assert_eq!(token_balance(&env.svm, &maker_ata_b), 5_000_000);
+    assert_eq!(token_balance(&env.svm, &taker_ata_b), 10_000_000 - 5_050_506);
+    assert!(is_closed(&env.svm, &escrow));
+
+    // The fee withheld in the vault on deposit is harvested so the vault can close
+    assert!(is_closed(&env.svm, &vault));
+    assert_eq!(withheld_fees(&env.svm, &env.mint_a), 100_000);
+}
+
+#[test]
+fn test_take_with_transfer_fee_without_receive_net() {
+    let mut env = setup_token_2022(Some(FEE_BPS), None);
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let make_ix = make_instruction(&env, 71, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+    let take_ix = take_instruction(&env, &taker.pubkey(), 71, 5_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+
+    // The fee is withheld from what the maker receives
+    let maker_ata_b = ata(&env, &maker.pubkey(), &env.mint_b);
+    assert_eq!(token_balance(&env.svm, &maker_ata_b), 4_950_000);
+}
+
+/// Loads the counting transfer hook and registers its extra accounts for `mint`.
+/// Returns the accounts to append to escrow instructions moving `mint`.
+fn setup_transfer_hook(env: &mut TestEnv, mint: &Pubkey) -> Vec<AccountMeta> {
+    let hook_program = transfer_hook_counter::ID;
+    let (extra_account_meta_list, _) = Pubkey::find_program_address(
+        &[b"extra-account-metas", mint.as_ref()],
+        &hook_program,
+    );
+    let (counter, _) =
+        Pubkey::find_program_address(&[b"counter", mint.as_ref()], &hook_program);
+
+    let init_ix = Instruction {
+        program_id: hook_program,
+        accounts: transfer_hook_counter::accounts::InitializeExtraAccountMetaList {
+            payer: env.maker.pubkey(),
+            mint: *mint,
+            extra_account_meta_list,
+            counter,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: transfer_hook_counter::instruction::InitializeExtraAccountMetaList {}.data(),
+    };
+    let maker = env.maker.insecure_clone();
+    send(&mut env.svm, init_ix, &maker).unwrap();
+
+    vec![
+        AccountMeta::new_readonly(extra_account_meta_list, false),
+        AccountMeta::new(counter, false),
+        AccountMeta::new_readonly(hook_program, false),
+    ]
+}
+
+fn hook_transfers(env: &TestEnv, mint: &Pubkey) -> u64 {
+    let (counter, _) =
+        Pubkey::find_program_address(&[b"counter", mint.as_ref()], &transfer_hook_counter::ID);
+    let account = env.svm.get_account(&counter).unwrap();
+    transfer_hook_counter::Counter::try_deserialize(&mut account.data.as_slice())
+        .unwrap()
+        .transfers
+}
+
+#[test]
+fn test_make_take_and_refund_with_transfer_hook() {
+    let mut env = setup_token_2022(None, Some(transfer_hook_counter::ID));
+    env.svm.add_program(
+        transfer_hook_counter::ID,
+        include_bytes!("../target/deploy/transfer_hook_counter.so"),
+    );
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+    let mint_a = env.mint_a;
+    let hook_accounts = setup_transfer_hook(&mut env, &mint_a);
+
+    // Without the extra accounts the hook cannot run and the deposit fails
+    let make_ix = make_instruction(&env, 80, MakeOptions::default());
+    assert!(send(&mut env.svm, make_ix, &maker).is_err());
+
+    let mut make_ix = make_instruction(&env, 80, MakeOptions::default());
+    make_ix.accounts.extend(hook_accounts.clone());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+    assert_eq!(hook_transfers(&env, &mint_a), 1);
+
+    let mut take_ix = take_instruction(&env, &taker.pubkey(), 80, 5_000_000, vec![]);
+    take_ix.accounts.extend(hook_accounts.clone());
+    send(&mut env.svm, take_ix, &taker).unwrap();
+    assert_eq!(hook_transfers(&env, &mint_a), 2);
+    assert_eq!(token_balance(&env.svm, &ata(&env, &taker.pubkey(), &mint_a)), 10_000_000);
+
+    let (escrow, _) = escrow_address(&env, 81);
+    let mut make_ix = make_instruction(&env, 81, MakeOptions::default());
+    make_ix.accounts.extend(hook_accounts.clone());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let escrow_state = decode_escrow(&env.svm.get_account(&escrow).unwrap().data).unwrap();
+    let mut refund_ix = refund_ix(&escrow_state, &env.token_program);
+    refund_ix.accounts.extend(hook_accounts);
+    send(&mut env.svm, refund_ix, &maker).unwrap();
+
+    assert_eq!(hook_transfers(&env, &mint_a), 4);
+    assert_eq!(token_balance(&env.svm, &env.maker_ata_a), 10_000_000);
+    assert!(is_closed(&env.svm, &escrow));
+}
+
+#[test]
+fn test_update_escrow_top_up_with_transfer_fee() {
+    let mut env = setup_token_2022(Some(FEE_BPS), None);
+    let maker = env.maker.insecure_clone();
+    let taker = env.taker.insecure_clone();
+
+    let (escrow, vault) = escrow_address(&env, 72);
+    let make_ix = make_instruction(&env, 72, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    // Only what reaches the vault after the fee is added to the deposit
+    let update_ix =
+        update_escrow_instruction(&env, 72, None, None, Some(DepositChange::TopUp(5_000_000)));
+    send(&mut env.svm, update_ix, &maker).unwrap();
+    assert_eq!(token_balance(&env.svm, &vault), 14_850_000);
+    assert_eq!(escrow_state(&env, 72).remaining_deposit, 14_850_000);
+
+    // So the whole vault can still be released on the final take
+    let take_ix = take_instruction(&env, &taker.pubkey(), 72, 5_000_000, vec![]);
+    send(&mut env.svm, take_ix, &taker).unwrap();
+    assert_eq!(token_balance(&env.svm, &ata(&env, &taker.pubkey(), &env.mint_a)), 14_701_500);
+    assert!(is_closed(&env.svm, &escrow));
+    assert!(is_closed(&env.svm, &vault));
+    assert_eq!(withheld_fees(&env.svm, &env.mint_a), 150_000);
+}
+
+#[test]
+fn test_refund_with_transfer_fee_closes_vault() {
+    let mut env = setup_token_2022(Some(FEE_BPS), None);
+    let maker = env.maker.insecure_clone();
+
+    let (escrow, vault) = escrow_address(&env, 73);
+    let make_ix = make_instruction(&env, 73, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let refund_ix = refund_ix(&escrow_state(&env, 73), &env.token_program);
+    send(&mut env.svm, refund_ix, &maker).unwrap();
+
+    // 1% is charged on the way in and again on the way out
+    assert_eq!(token_balance(&env.svm, &env.maker_ata_a), 10_000_000 + 9_801_000);
+    assert!(is_closed(&env.svm, &escrow));
+    assert!(is_closed(&env.svm, &vault));
+    assert_eq!(withheld_fees(&env.svm, &env.mint_a), 100_000);
+}
+
+#[test]
+fn test_update_escrow_with_transfer_hook() {
+    let mut env = setup_token_2022(None, Some(transfer_hook_counter::ID));
+    env.svm.add_program(
+        transfer_hook_counter::ID,
+        include_bytes!("../target/deploy/transfer_hook_counter.so"),
+    );
+    let maker = env.maker.insecure_clone();
+    let mint_a = env.mint_a;
+    let hook_accounts = setup_transfer_hook(&mut env, &mint_a);
+
+    let mut make_ix = make_instruction(&env, 82, MakeOptions::default());
+    make_ix.accounts.extend(hook_accounts.clone());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let mut update_ix =
+        update_escrow_instruction(&env, 82, None, None, Some(DepositChange::TopUp(1_000_000)));
+    update_ix.accounts.extend(hook_accounts.clone());
+    send(&mut env.svm, update_ix, &maker).unwrap();
+    assert_eq!(hook_transfers(&env, &mint_a), 2);
+
+    let mut update_ix =
+        update_escrow_instruction(&env, 82, None, None, Some(DepositChange::Withdraw(3_000_000)));
+    update_ix.accounts.extend(hook_accounts);
+    send(&mut env.svm, update_ix, &maker).unwrap();
+    assert_eq!(hook_transfers(&env, &mint_a), 3);
+    assert_eq!(escrow_state(&env, 82).remaining_deposit, 8_000_000);
+}
+
+// ============================================================================
+// Adversarial tests, run against both token programs where it matters
+// ============================================================================
+
+fn both_token_programs() -> [TestEnv; 2] {
+    [setup(), setup_token_2022(None, None)]
+}
+
+fn replace_account(instruction: &mut Instruction, address: &Pubkey, replacement: AccountMeta) {
+    for meta in instruction.accounts.iter_mut().filter(|meta| &meta.pubkey == address) {
+        *meta = replacement.clone();
+    }
+}
+
+fn escrow_state(env: &TestEnv, seed: u64) -> Escrow {
+    let (escrow, _) = escrow_address(env, seed);
+    decode_escrow(&env.svm.get_account(&escrow).unwrap().data).unwrap()
+}
+
+#[test]
+fn test_take_with_wrong_mint_b_fails() {
+    for mut env in both_token_programs() {
+        let maker = env.maker.insecure_clone();
+        let taker = env.taker.insecure_clone();
+        let make_ix = make_instruction(&env, 90, MakeOptions::default());
+        send(&mut env.svm, make_ix, &maker).unwrap();
+
+        // The taker pays with a worthless mint of their own
+        let fake_mint = CreateMint::new(&mut env.svm, &taker)
+            .authority(&taker.pubkey())
+            .decimals(DECIMALS)
+            .token_program_id(&env.token_program)
+            .send()
+            .unwrap();
+        let fake_ata = CreateAssociatedTokenAccount::new(&mut env.svm, &taker, &fake_mint)
+            .owner(&taker.pubkey())
+            .token_program_id(&env.token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut env.svm, &taker, &fake_mint, &fake_ata, 5_000_000)
+            .token_program_id(&env.token_program)
+            .send()
+            .unwrap();
+
+        let mut forged = escrow_state(&env, 90);
+        forged.mint_b = fake_mint;
+        let take_ix = take_ix(
+            &taker.pubkey(),
+            &forged,
+            &env.token_program,
+            instruction::Take {
+                amount: 5_000_000,
+                proof: vec![],
+            },
+        );
+        // Only the mint is forged, the order book is the real pair's
+        let mut take_ix = take_ix;
+        replace_account(
+            &mut take_ix,
+            &order_book_address(&env.mint_a, &fake_mint),
+            AccountMeta::new(order_book_address(&env.mint_a, &env.mint_b), false),
+        );
+
+        assert_anchor_error(send(&mut env.svm, take_ix, &taker), ErrorCode::ConstraintHasOne);
+    }
+}
+
+#[test]
+fn test_refund_by_third_party_fails() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let make_ix = make_instruction(&env, 91, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let attacker = Keypair::new();
+    env.svm.airdrop(&attacker.pubkey(), 1_000_000_000).unwrap(); // 1 SOL
+    let attacker_ata_a = CreateAssociatedTokenAccount::new(&mut env.svm, &attacker, &env.mint_a)
+        .owner(&attacker.pubkey())
+        .send()
+        .unwrap();
+
+    // Same accounts as the maker's refund, signed by the attacker into their own ATA
+    let mut refund_ix = refund_ix(&escrow_state(&env, 91), &env.token_program);
+    replace_account(&mut refund_ix, &maker.pubkey(), AccountMeta::new(attacker.pubkey(), true));
+    replace_account(
+        &mut refund_ix,
+        &env.maker_ata_a,
+        AccountMeta::new(attacker_ata_a, false),
+    );
+
+    assert_anchor_error(send(&mut env.svm, refund_ix, &attacker), ErrorCode::ConstraintSeeds);
+    let (_, vault) = escrow_address(&env, 91);
+    assert_eq!(token_balance(&env.svm, &vault), 10_000_000);
+}
+
+#[test]
+fn test_second_take_after_close_fails() {
+    for mut env in both_token_programs() {
+        let maker = env.maker.insecure_clone();
+        let taker = env.taker.insecure_clone();
+        let make_ix = make_instruction(&env, 92, MakeOptions::default());
+        send(&mut env.svm, make_ix, &maker).unwrap();
+        let escrow = escrow_state(&env, 92);
+
+        let take = |amount| instruction::Take {
+            amount,
+            proof: vec![],
+        };
+        let take_ix = take_ix(&taker.pubkey(), &escrow, &env.token_program, take(5_000_000));
+        send(&mut env.svm, take_ix.clone(), &taker).unwrap();
+
+        // Replaying the same take once the escrow is closed
+        env.svm.expire_blockhash();
+        assert_anchor_error(
+            send(&mut env.svm, take_ix, &taker),
+            ErrorCode::AccountNotInitialized,
+        );
+    }
+}
+
+#[test]
+fn test_make_with_zero_receive_fails() {
+    for mut env in both_token_programs() {
+        let maker = env.maker.insecure_clone();
+        let make_ix = make_ix(
+            &maker.pubkey(),
+            &env.mint_a,
+            &env.mint_b,
+            &env.token_program,
+            instruction::Make {
+                seed: 93,
+                deposit: 10_000_000,
+                receive: 0,
+                options: MakeOptions::default(),
+            },
+        );
+
+        assert_escrow_error(send(&mut env.svm, make_ix, &maker), EscrowError::InvalidAmount);
+    }
+}
+
+#[test]
+fn test_maker_taking_own_escrow_fails() {
+    let mut env = setup();
+    let maker = env.maker.insecure_clone();
+    let make_ix = make_instruction(&env, 94, MakeOptions::default());
+    send(&mut env.svm, make_ix, &maker).unwrap();
+
+    let take_ix = take_instruction(&env, &maker.pubkey(), 94, 5_000_000, vec![]);
+
+    assert_escrow_error(send(&mut env.svm, take_ix, &maker), EscrowError::MakerIsTaker);
+}
+
+#[test]
+fn test_make_with_mismatched_token_program_fails() {
+    // Token-2022 mints passed with the legacy token program
+    let mut env = setup_token_2022(None, None);
+    let maker = env.maker.insecure_clone();
+    env.token_program = anchor_spl::token::ID;
+    let make_ix = make_instruction(&env, 95, MakeOptions::default());
+
+    assert_anchor_error(
+        send(&mut env.svm, make_ix, &maker),
+        ErrorCode::ConstraintMintTokenProgram,
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    /// However an order is split into partial fills, the taker never gets more than
+    /// the deposit, pays exactly `receive` in total, and the last fill empties the vault.
+    #[test]
+    fn prop_partial_fills_release_the_whole_deposit(
+        deposit in 1_000u64..=10_000_000,
+        receive in 1_000u64..=5_000_000,
+        cuts in proptest::collection::vec(1u64..1_000, 1..5),
+    ) {
+        let mut env = setup();
+        let maker = env.maker.insecure_clone();
+        let taker = env.taker.insecure_clone();
+
+        let make_ix = make_ix(
+            &maker.pubkey(),
+            &env.mint_a,
+            &env.mint_b,
+            &env.token_program,
+            instruction::Make {
+                seed: 96,
+                deposit,
+                receive,
+                options: MakeOptions {
+                    partial_fill: true,
+                    ..MakeOptions::default()
+                },
+            },
+        );
+        send(&mut env.svm, make_ix, &maker).unwrap();
+
+        // Split `receive` into fills proportional to `cuts`, the last one taking the rest
+        let total_cuts: u64 = cuts.iter().sum();
+        let mut fills: Vec<u64> = cuts
+            .iter()
+            .map(|cut| receive * cut / total_cuts)
+            .filter(|fill| *fill > 0)
+            .collect();
+        fills.pop();
+        fills.push(receive - fills.iter().sum::<u64>());
+
+        let (escrow, vault) = escrow_address(&env, 96);
+        let taker_ata_a = ata(&env, &taker.pubkey(), &env.mint_a);
+        for fill in fills {
+            let take_ix = take_instruction(&env, &taker.pubkey(), 96, fill, vec![]);
+            match send(&mut env.svm, take_ix, &taker) {
+                Ok(()) => {}
+                // Fills worth less than one base unit of mint_a are rejected
+                Err(err) => {
+                    assert_escrow_error(Err(err), EscrowError::FillTooSmall);
+                    return Ok(());
+                }
+            }
+            prop_assert!(token_balance(&env.svm, &taker_ata_a) <= deposit);
+        }
+
+        prop_assert_eq!(token_balance(&env.svm, &taker_ata_a), deposit);
+        prop_assert_eq!(
+            token_balance(&env.svm, &ata(&env, &maker.pubkey(), &env.mint_b)),
+            receive
+        );
+        prop_assert!(is_closed(&env.svm, &vault));
+        prop_assert!(is_closed(&env.svm, &escrow));
+    }
+}
@@ -22,4 +22,24 @@ pub enum StakeError {
     NotOwner,
     #[msg("No Points To Claim")]
     NoPointsToClaim,
+    #[msg("Arithmetic Overflow")]
+    Overflow,
+    #[msg("Invalid Stake Account")]
+    InvalidStakeAccount,
+    #[msg("Staking Paused")]
+    StakingPaused,
+    #[msg("Collection Not Whitelisted")]
+    CollectionNotWhitelisted,
+    #[msg("Collection Whitelist Full")]
+    CollectionWhitelistFull,
+    #[msg("Too Many Multipliers")]
+    TooManyMultipliers,
+    #[msg("Attribute Too Long")]
+    AttributeTooLong,
+    #[msg("Invalid Amount")]
+    InvalidAmount,
+    #[msg("Rewards Are Paid From The Reward Pool")]
+    RewardPoolEnabled,
+    #[msg("Reward Pool Empty")]
+    RewardPoolEmpty,
 }
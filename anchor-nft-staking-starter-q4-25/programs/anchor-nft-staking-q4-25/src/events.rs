@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct Staked {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub collection: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    /// Points accrued by the stake since its last claim, credited on unstake.
+    pub points: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Claimed {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -4,7 +4,11 @@ use anchor_spl::{
     token::{mint_to, Mint, MintTo, Token, TokenAccount},
 };
 
-use crate::{errors::StakeError, state::{StakeConfig, UserAccount}};
+use crate::{
+    errors::StakeError,
+    events::Claimed,
+    state::{StakeAccount, StakeConfig, StakeStats, UserAccount},
+};
 
 #[derive(Accounts)]
 pub struct Claim<'info> {
@@ -36,24 +40,43 @@ pub struct Claim<'info> {
     #[account(
         seeds = [b"config".as_ref()],
         bump = config.bump,
+        constraint = config.reward_pool.is_none() @ StakeError::RewardPoolEnabled,
     )]
     pub config: Account<'info, StakeConfig>,
 
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Claim<'info> {
-    pub fn claim(&mut self) -> Result<()> {
+    /// Accrues points on the user's stake accounts passed as remaining accounts before
+    /// minting. Accrual is tracked per stake, so any left out keep their points for later.
+    pub fn claim(&mut self, stake_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        accrue_stakes(&self.user, &mut self.user_account, &self.config, stake_accounts)?;
+
         require!(self.user_account.points > 0, StakeError::NoPointsToClaim);
 
         // allow 1 to 1 conversion of points to rewards
-        let amount = self.user_account.points as u64;
+        let amount = self.user_account.points;
 
         self.mint_rewards(amount)?;
 
-        self.user_account.points = 0;
+        self.user_account.record_claim(amount)?;
+        self.stats.record_claim(amount)?;
+
+        emit!(Claimed {
+            user: self.user.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -75,3 +98,28 @@ impl<'info> Claim<'info> {
         ), amount)
     }
 }
+
+/// Accrues points into `user_account` from the user's stake accounts.
+pub fn accrue_stakes<'info>(
+    user: &Signer<'info>,
+    user_account: &mut UserAccount,
+    config: &StakeConfig,
+    stake_accounts: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    for account in stake_accounts {
+        require!(account.is_writable, StakeError::InvalidStakeAccount);
+
+        let mut stake_account = Account::<StakeAccount>::try_from(account)?;
+        require_keys_eq!(stake_account.owner, user.key(), StakeError::InvalidStakeAccount);
+
+        let points = stake_account.accrue(config, now)?;
+        user_account.add_points(points)?;
+
+        // Written back right away so a duplicate entry accrues nothing
+        stake_account.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    errors::StakeError,
+    events::Claimed,
+    instructions::accrue_stakes,
+    state::{RewardPool, StakeConfig, StakeStats, UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct ClaimFromPool<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_token,
+        associated_token::authority = user,
+    )]
+    pub rewards_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user".as_ref(), user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
+    #[account(
+        mut,
+        constraint = reward_pool.mint == reward_token.key(),
+        seeds = [b"pool".as_ref(), config.key().as_ref()],
+        bump = reward_pool.bump,
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub reward_token: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_token,
+        associated_token::authority = reward_pool,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimFromPool<'info> {
+    /// Pays out as many points as the vault balance and the emission cap allow, 1 to 1.
+    /// Points that can't be paid yet stay on the user account for a later claim.
+    pub fn claim_from_pool(&mut self, stake_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        accrue_stakes(&self.user, &mut self.user_account, &self.config, stake_accounts)?;
+
+        require!(self.user_account.points > 0, StakeError::NoPointsToClaim);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.reward_pool.checkpoint(now)?;
+
+        let amount = self.user_account.points
+            .min(self.reward_pool.emittable())
+            .min(self.vault.amount);
+        require!(amount > 0, StakeError::RewardPoolEmpty);
+
+        self.transfer_rewards(amount)?;
+
+        self.user_account.record_claim(amount)?;
+        self.reward_pool.record_emission(amount)?;
+        self.stats.record_claim(amount)?;
+
+        emit!(Claimed {
+            user: self.user.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_rewards(&mut self, amount: u64) -> Result<()> {
+        let config = self.config.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"pool",
+            config.as_ref(),
+            &[self.reward_pool.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.reward_token.to_account_info(),
+                    to: self.rewards_ata.to_account_info(),
+                    authority: self.reward_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            self.reward_token.decimals,
+        )
+    }
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked};
+
+use crate::{
+    errors::StakeError,
+    state::{RewardPool, StakeConfig},
+};
+
+#[derive(Accounts)]
+pub struct FundRewardPool<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_token,
+        associated_token::authority = admin,
+    )]
+    pub admin_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        constraint = reward_pool.mint == reward_token.key(),
+        seeds = [b"pool".as_ref(), config.key().as_ref()],
+        bump = reward_pool.bump,
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub reward_token: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_token,
+        associated_token::authority = reward_pool,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> FundRewardPool<'info> {
+    pub fn fund_reward_pool(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, StakeError::InvalidAmount);
+
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.admin_ata.to_account_info(),
+                    mint: self.reward_token.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.admin.to_account_info(),
+                },
+            ),
+            amount,
+            self.reward_token.decimals,
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token};
 
-use crate::state::StakeConfig;
+use crate::state::{StakeConfig, StakeStats};
 
 #[derive(Accounts)]
 pub struct InitializeConfig<'info> {
@@ -27,6 +27,15 @@ pub struct InitializeConfig<'info> {
     )]
     pub reward_mint: Account<'info, Mint>,
 
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump,
+        space = StakeStats::DISCRIMINATOR.len() + StakeStats::INIT_SPACE,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
@@ -40,13 +49,27 @@ impl<'info> InitializeConfig<'info> {
         bumps: &InitializeConfigBumps,
     ) -> Result<()> {
         self.config.set_inner(StakeConfig {
+            admin: self.admin.key(),
             points_per_stake,
+            reward_index: 0,
+            index_updated_at: Clock::get()?.unix_timestamp,
             max_stake,
             freeze_period,
+            paused: false,
+            collections: Vec::new(),
+            legacy_collections: Vec::new(),
+            multipliers: Vec::new(),
+            reward_pool: None,
             rewards_bump: bumps.reward_mint,
             bump: bumps.config,
         });
 
+        self.stats.set_inner(StakeStats {
+            total_staked: 0,
+            total_emitted: 0,
+            bump: bumps.stats,
+        });
+
         Ok(())
     }
 }
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Mint, Token, TokenAccount},
+};
+
+use crate::state::{RewardPool, StakeConfig};
+
+#[derive(Accounts)]
+pub struct InitializeRewardPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool".as_ref(), config.key().as_ref()],
+        space = RewardPool::DISCRIMINATOR.len() + RewardPool::INIT_SPACE,
+        bump,
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub reward_token: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = reward_token,
+        associated_token::authority = reward_pool,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeRewardPool<'info> {
+    /// Switches rewards over to the pool for good; `claim` stops minting from here on.
+    pub fn initialize_reward_pool(&mut self, emission_per_second: u64, bump: u8) -> Result<()> {
+        self.reward_pool.set_inner(RewardPool {
+            mint: self.reward_token.key(),
+            emission_per_second,
+            released: 0,
+            emitted: 0,
+            last_update: Clock::get()?.unix_timestamp,
+            bump,
+        });
+
+        self.config.reward_pool = Some(self.reward_pool.key());
+
+        Ok(())
+    }
+}
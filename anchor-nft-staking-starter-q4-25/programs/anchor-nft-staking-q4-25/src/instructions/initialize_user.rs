@@ -24,6 +24,9 @@ impl<'info> Initialize<'info> {
         self.user_account.set_inner(UserAccount {
             points: 0,
             amount_staked: 0,
+            total_earned: 0,
+            total_claimed: 0,
+            first_staked_at: 0,
             bump: bumps.user_account,
         });
 
@@ -16,5 +16,44 @@ pub use stake::*;
 pub mod unstake;
 pub use unstake::*;
 
+pub mod stake_many;
+pub use stake_many::*;
+
+pub mod unstake_many;
+pub use unstake_many::*;
+
+pub mod stake_legacy;
+pub use stake_legacy::*;
+
+pub mod unstake_legacy;
+pub use unstake_legacy::*;
+
 pub mod claim;
 pub use claim::*;
+
+pub mod initialize_reward_pool;
+pub use initialize_reward_pool::*;
+
+pub mod fund_reward_pool;
+pub use fund_reward_pool::*;
+
+pub mod set_emission_rate;
+pub use set_emission_rate::*;
+
+pub mod claim_from_pool;
+pub use claim_from_pool::*;
+
+pub mod update_config;
+pub use update_config::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
+pub mod set_collection_reward;
+pub use set_collection_reward::*;
+
+pub mod remove_collection_reward;
+pub use remove_collection_reward::*;
+
+pub mod set_attribute_multiplier;
+pub use set_attribute_multiplier::*;
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{delist_collection, StakeConfig};
+
+#[derive(Accounts)]
+pub struct RemoveCollectionReward<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+}
+
+impl<'info> RemoveCollectionReward<'info> {
+    /// Delists `collection`: it can't be staked anymore and its stakes stop earning,
+    /// keeping what they earned so far. Its entry keeps its whitelist slot.
+    /// Delisting the last listed collection reopens staking to every registered
+    /// collection without an entry.
+    pub fn remove_collection_reward(&mut self, collection: Pubkey) -> Result<()> {
+        self.config.checkpoint(Clock::get()?.unix_timestamp)?;
+
+        delist_collection(&mut self.config.collections, collection)
+    }
+
+    /// Delists the legacy `collection`, as `remove_collection_reward` does for core ones.
+    pub fn remove_legacy_collection_reward(&mut self, collection: Pubkey) -> Result<()> {
+        self.config.checkpoint(Clock::get()?.unix_timestamp)?;
+
+        delist_collection(&mut self.config.legacy_collections, collection)
+    }
+}
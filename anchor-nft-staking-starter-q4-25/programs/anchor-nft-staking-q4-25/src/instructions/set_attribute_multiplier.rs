@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::StakeError,
+    state::{AttributeMultiplier, StakeConfig, MAX_MULTIPLIERS},
+};
+
+#[derive(Accounts)]
+pub struct SetAttributeMultiplier<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+}
+
+impl<'info> SetAttributeMultiplier<'info> {
+    /// Sets the multiplier for assets with the `key`=`value` attribute, in basis points.
+    /// A multiplier of 0 removes it. Only assets staked afterwards are affected.
+    pub fn set_attribute_multiplier(&mut self, key: String, value: String, multiplier_bps: u16) -> Result<()> {
+        require!(key.len() <= 32 && value.len() <= 64, StakeError::AttributeTooLong);
+
+        let multipliers = &mut self.config.multipliers;
+        let index = multipliers
+            .iter()
+            .position(|multiplier| multiplier.key == key && multiplier.value == value);
+
+        match (index, multiplier_bps) {
+            (Some(index), 0) => {
+                multipliers.remove(index);
+            }
+            (Some(index), _) => multipliers[index].multiplier_bps = multiplier_bps,
+            (None, 0) => {}
+            (None, _) => {
+                require!(multipliers.len() < MAX_MULTIPLIERS, StakeError::TooManyMultipliers);
+                multipliers.push(AttributeMultiplier {
+                    key,
+                    value,
+                    multiplier_bps,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{list_collection, StakeConfig};
+
+#[derive(Accounts)]
+pub struct SetCollectionReward<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+}
+
+impl<'info> SetCollectionReward<'info> {
+    /// Whitelists `collection` or updates its reward rate, from now on.
+    pub fn set_collection_reward(&mut self, collection: Pubkey, points_per_stake: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.config.checkpoint(now)?;
+
+        // Stakes made at the base rate carry on from the base index
+        let reward_index = self.config.reward_index;
+        list_collection(&mut self.config.collections, collection, points_per_stake, reward_index, now)
+    }
+
+    /// Whitelists the legacy Token Metadata `collection` or updates its reward rate,
+    /// from now on. Core collections are unaffected.
+    pub fn set_legacy_collection_reward(&mut self, collection: Pubkey, points_per_stake: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.config.checkpoint(now)?;
+
+        list_collection(&mut self.config.legacy_collections, collection, points_per_stake, 0, now)
+    }
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{RewardPool, StakeConfig};
+
+#[derive(Accounts)]
+pub struct SetEmissionRate<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"pool".as_ref(), config.key().as_ref()],
+        bump = reward_pool.bump,
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+}
+
+impl<'info> SetEmissionRate<'info> {
+    pub fn set_emission_rate(&mut self, emission_per_second: u64) -> Result<()> {
+        self.reward_pool.checkpoint(Clock::get()?.unix_timestamp)?;
+        self.reward_pool.emission_per_second = emission_per_second;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StakeConfig;
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+}
+
+impl<'info> SetPaused<'info> {
+    /// Pausing only blocks new stakes; unstake and claim keep working.
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.config.paused = paused;
+
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
 use anchor_lang::prelude::*;
 use mpl_core::{
+    accounts::BaseAssetV1,
+    fetch_plugin,
     instructions::AddPluginV1CpiBuilder,
-    types::{FreezeDelegate, Plugin, PluginAuthority},
+    types::{Attributes, FreezeDelegate, Plugin, PluginAuthority, PluginType, UpdateAuthority},
     ID as CORE_PROGRAM_ID,
 };
 
 use crate::{
     errors::StakeError,
-    state::{StakeAccount, StakeConfig, UserAccount},
+    events::Staked,
+    state::{CollectionInfo, StakeAccount, StakeConfig, StakeStats, UserAccount},
 };
 
 #[derive(Accounts)]
@@ -31,6 +34,12 @@ pub struct Stake<'info> {
     /// CHECK: Verified by mpl-core
     pub collection: UncheckedAccount<'info>,
 
+    #[account(
+        seeds = [b"collection_info", collection.key().as_ref()],
+        bump = collection_info.bump,
+    )]
+    pub collection_info: Account<'info, CollectionInfo>,
+
     #[account(
         mut,
         seeds = [b"user".as_ref(), user.key().as_ref()],
@@ -50,9 +59,17 @@ pub struct Stake<'info> {
     #[account(
         seeds = [b"config".as_ref()],
         bump = config.bump,
+        constraint = !config.paused @ StakeError::StakingPaused,
     )]
     pub config: Account<'info, StakeConfig>,
 
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
     #[account(address = CORE_PROGRAM_ID)]
     /// CHECK: Verified by address constraint
     pub core_program: UncheckedAccount<'info>,
@@ -64,25 +81,91 @@ impl<'info> Stake<'info> {
     pub fn stake(&mut self, bump: u8) -> Result<()> {
         require!(self.user_account.amount_staked < self.config.max_stake, StakeError::MaxStakeReached);
 
+        check_asset(&self.asset, &self.user.key(), &self.collection_info.collection)?;
+        require!(
+            self.config.points_per_stake_for(&self.collection_info.collection).is_some(),
+            StakeError::CollectionNotWhitelisted
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
         self.stake_account.set_inner(StakeAccount {
             owner: self.user.key(),
             mint: self.asset.key(),
-            staked_at: Clock::get()?.unix_timestamp,
+            collection: self.collection_info.collection,
+            staked_at: now,
+            last_claimed: now,
+            reward_index: self.config.reward_index_for(&self.collection_info.collection, now)?,
+            multiplier_bps: multiplier_bps(&self.config, &self.asset),
             bump,
         });
 
         self.user_account.amount_staked += 1;
+        self.user_account.record_stake(now);
+        self.stats.total_staked += 1;
+
+        emit!(Staked {
+            user: self.user.key(),
+            mint: self.asset.key(),
+            collection: self.collection_info.collection,
+            timestamp: now,
+        });
 
-        AddPluginV1CpiBuilder::new(&self.core_program.to_account_info())
-            .asset(&self.asset.to_account_info())
-            .collection(Some(&self.collection.to_account_info()))
-            .payer(&self.user.to_account_info())
-            .authority(None)
-            .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: true }))
-            .init_authority(PluginAuthority::Address { address: self.stake_account.key() })
-            .system_program(&self.system_program.to_account_info())
-            .invoke()?;
-
-        Ok(())
+        freeze_asset(
+            &self.core_program,
+            &self.asset,
+            &self.collection,
+            &self.user,
+            &self.system_program,
+            self.stake_account.key(),
+        )
     }
 }
+
+/// Checks the asset is an mpl-core asset owned by `owner` and part of `collection`.
+pub fn check_asset(asset: &AccountInfo, owner: &Pubkey, collection: &Pubkey) -> Result<()> {
+    require_keys_eq!(*asset.owner, CORE_PROGRAM_ID, StakeError::InvalidAsset);
+
+    let asset = BaseAssetV1::from_bytes(&asset.data.borrow())
+        .map_err(|_| StakeError::InvalidAsset)?;
+
+    require_keys_eq!(asset.owner, *owner, StakeError::NotOwner);
+    require!(
+        asset.update_authority == UpdateAuthority::Collection(*collection),
+        StakeError::InvalidCollection
+    );
+
+    Ok(())
+}
+
+/// Reward multiplier from the asset's Attributes plugin. Assets without one earn
+/// the base rate.
+pub fn multiplier_bps(config: &StakeConfig, asset: &AccountInfo) -> u16 {
+    let attributes = fetch_plugin::<BaseAssetV1, Attributes>(asset, PluginType::Attributes)
+        .map(|(_, attributes, _)| attributes.attribute_list)
+        .unwrap_or_default();
+
+    config.multiplier_bps_for(&attributes)
+}
+
+/// Freezes the asset with a FreezeDelegate plugin whose authority is the stake account.
+pub fn freeze_asset<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    stake_account: Pubkey,
+) -> Result<()> {
+    AddPluginV1CpiBuilder::new(core_program)
+        .asset(asset)
+        .collection(Some(collection))
+        .payer(owner)
+        .authority(None)
+        .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: true }))
+        .init_authority(PluginAuthority::Address { address: stake_account })
+        .system_program(system_program)
+        .invoke()?;
+
+    Ok(())
+}
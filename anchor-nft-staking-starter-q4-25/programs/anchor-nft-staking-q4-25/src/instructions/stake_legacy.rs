@@ -0,0 +1,153 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        freeze_delegated_account, FreezeDelegatedAccount, MasterEditionAccount, Metadata,
+        MetadataAccount,
+    },
+    token::{approve, Approve, Mint, Token, TokenAccount},
+};
+
+use crate::{
+    errors::StakeError,
+    events::Staked,
+    state::{StakeAccount, StakeConfig, StakeStats, UserAccount, BPS_SCALE},
+};
+
+/// Stakes a legacy Token Metadata NFT by freezing the user's token account, with the
+/// stake account PDA as the freeze delegate. The NFT never leaves the user's wallet.
+#[derive(Accounts)]
+pub struct StakeLegacy<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+    )]
+    pub user_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub edition: Account<'info, MasterEditionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user".as_ref(), user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"stake", config.key().as_ref(), mint.key().as_ref()],
+        space = StakeAccount::DISCRIMINATOR.len() + StakeAccount::INIT_SPACE,
+        bump,
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ StakeError::StakingPaused,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeLegacy<'info> {
+    pub fn stake_legacy(&mut self, bump: u8) -> Result<()> {
+        require!(self.user_account.amount_staked < self.config.max_stake, StakeError::MaxStakeReached);
+        require!(self.user_ata.amount == 1, StakeError::NotOwner);
+
+        // Legacy collections aren't registered here, so they must be whitelisted explicitly
+        // in their own list
+        let collection = match &self.metadata.collection {
+            Some(collection) if collection.verified => collection.key,
+            _ => return err!(StakeError::InvalidCollection),
+        };
+        require!(
+            self.config.legacy_collection_reward(&collection).is_some_and(|reward| reward.listed),
+            StakeError::CollectionNotWhitelisted
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        self.stake_account.set_inner(StakeAccount {
+            owner: self.user.key(),
+            mint: self.mint.key(),
+            collection,
+            staked_at: now,
+            last_claimed: now,
+            reward_index: self.config.reward_index_for(&collection, now)?,
+            multiplier_bps: BPS_SCALE,
+            bump,
+        });
+
+        self.user_account.amount_staked += 1;
+        self.user_account.record_stake(now);
+        self.stats.total_staked += 1;
+
+        emit!(Staked {
+            user: self.user.key(),
+            mint: self.mint.key(),
+            collection,
+            timestamp: now,
+        });
+
+        approve(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                Approve {
+                    to: self.user_ata.to_account_info(),
+                    delegate: self.stake_account.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"stake",
+            &self.config.key().to_bytes(),
+            &self.mint.key().to_bytes(),
+            &[bump],
+        ]];
+
+        freeze_delegated_account(CpiContext::new_with_signer(
+            self.metadata_program.to_account_info(),
+            FreezeDelegatedAccount {
+                metadata: self.metadata.to_account_info(),
+                delegate: self.stake_account.to_account_info(),
+                token_account: self.user_ata.to_account_info(),
+                edition: self.edition.to_account_info(),
+                mint: self.mint.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    }
+}
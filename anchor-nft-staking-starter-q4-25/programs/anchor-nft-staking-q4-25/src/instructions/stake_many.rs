@@ -0,0 +1,197 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{
+        allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+    },
+};
+use mpl_core::ID as CORE_PROGRAM_ID;
+
+use crate::{
+    errors::StakeError,
+    events::Staked,
+    instructions::{check_asset, freeze_asset, multiplier_bps},
+    state::{CollectionInfo, StakeAccount, StakeConfig, StakeStats, UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct StakeMany<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = collection.owner == &CORE_PROGRAM_ID @ StakeError::InvalidCollection,
+        constraint = !collection.data_is_empty() @ StakeError::CollectionNotInitialized
+    )]
+    /// CHECK: Verified by mpl-core
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"collection_info", collection.key().as_ref()],
+        bump = collection_info.bump,
+    )]
+    pub collection_info: Account<'info, CollectionInfo>,
+
+    #[account(
+        mut,
+        seeds = [b"user".as_ref(), user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ StakeError::StakingPaused,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
+    #[account(address = CORE_PROGRAM_ID)]
+    /// CHECK: Verified by address constraint
+    pub core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeMany<'info> {
+    /// Stakes every `[asset, stake_account]` pair in the remaining accounts, all from
+    /// the same collection.
+    pub fn stake_many(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len() % 2 == 0,
+            StakeError::InvalidStakeAccount
+        );
+
+        let count = remaining_accounts.len() / 2;
+        require!(
+            self.user_account.amount_staked as usize + count <= self.config.max_stake as usize,
+            StakeError::MaxStakeReached
+        );
+        require!(
+            self.config.points_per_stake_for(&self.collection_info.collection).is_some(),
+            StakeError::CollectionNotWhitelisted
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        for pair in remaining_accounts.chunks(2) {
+            let (asset, stake_account) = (&pair[0], &pair[1]);
+
+            check_asset(asset, &self.user.key(), &self.collection_info.collection)?;
+
+            self.init_stake_account(asset, stake_account, now)?;
+
+            freeze_asset(
+                &self.core_program,
+                asset,
+                &self.collection,
+                &self.user,
+                &self.system_program,
+                stake_account.key(),
+            )?;
+
+            emit!(Staked {
+                user: self.user.key(),
+                mint: asset.key(),
+                collection: self.collection_info.collection,
+                timestamp: now,
+            });
+        }
+
+        self.user_account.amount_staked += count as u8;
+        self.user_account.record_stake(now);
+        self.stats.total_staked += count as u64;
+
+        Ok(())
+    }
+
+    /// Creates the stake account PDA of `asset`. Like `init`, an address already
+    /// holding lamports is topped up to rent exemption, allocated and assigned instead,
+    /// so prefunding it can't block the stake.
+    fn init_stake_account(&self, asset: &AccountInfo<'info>, stake_account: &AccountInfo<'info>, now: i64) -> Result<()> {
+        let config = self.config.key();
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"stake", config.as_ref(), asset.key.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(stake_account.key(), address, StakeError::InvalidStakeAccount);
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"stake", config.as_ref(), asset.key.as_ref(), &[bump]]];
+        let space = StakeAccount::DISCRIMINATOR.len() + StakeAccount::INIT_SPACE;
+        let rent_exempt = Rent::get()?.minimum_balance(space);
+        let system_program = self.system_program.to_account_info();
+
+        if stake_account.lamports() == 0 {
+            create_account(
+                CpiContext::new_with_signer(
+                    system_program,
+                    CreateAccount {
+                        from: self.user.to_account_info(),
+                        to: stake_account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                rent_exempt,
+                space as u64,
+                &crate::ID,
+            )?;
+        } else {
+            require_keys_eq!(*stake_account.owner, self.system_program.key(), StakeError::InvalidStakeAccount);
+
+            let top_up = rent_exempt.saturating_sub(stake_account.lamports());
+            if top_up > 0 {
+                transfer(
+                    CpiContext::new(
+                        system_program.clone(),
+                        Transfer {
+                            from: self.user.to_account_info(),
+                            to: stake_account.clone(),
+                        },
+                    ),
+                    top_up,
+                )?;
+            }
+
+            allocate(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    Allocate {
+                        account_to_allocate: stake_account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                space as u64,
+            )?;
+
+            assign(
+                CpiContext::new_with_signer(
+                    system_program,
+                    Assign {
+                        account_to_assign: stake_account.clone(),
+                    },
+                    signer_seeds,
+                ),
+                &crate::ID,
+            )?;
+        }
+
+        let data = StakeAccount {
+            owner: self.user.key(),
+            mint: asset.key(),
+            collection: self.collection_info.collection,
+            staked_at: now,
+            last_claimed: now,
+            reward_index: self.config.reward_index_for(&self.collection_info.collection, now)?,
+            multiplier_bps: multiplier_bps(&self.config, asset),
+            bump,
+        };
+        data.try_serialize(&mut &mut stake_account.try_borrow_mut_data()?[..])
+    }
+}
@@ -7,7 +7,8 @@ use mpl_core::{
 
 use crate::{
     errors::StakeError,
-    state::{StakeAccount, StakeConfig, UserAccount},
+    events::Unstaked,
+    state::{StakeAccount, StakeConfig, StakeStats, UserAccount},
 };
 
 #[derive(Accounts)]
@@ -52,6 +53,13 @@ pub struct Unstake<'info> {
     )]
     pub config: Account<'info, StakeConfig>,
 
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
     #[account(address = CORE_PROGRAM_ID)]
     /// CHECK: Verified by address constraint
     pub core_program: UncheckedAccount<'info>,
@@ -61,7 +69,8 @@ pub struct Unstake<'info> {
 
 impl<'info> Unstake<'info> {
     pub fn unstake(&mut self) -> Result<()> {
-        let elapsed = Clock::get()?.unix_timestamp - self.stake_account.staked_at as i64;
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = now - self.stake_account.staked_at;
         require!(
             elapsed >= self.config.freeze_period as i64,
             StakeError::FreezePeriodNotPassed
@@ -77,26 +86,56 @@ impl<'info> Unstake<'info> {
         self.user_account.amount_staked -= 1;
 
         // allow to keep earning points after min freeze period
-        self.user_account.points += self.config.points_per_stake as u32 * elapsed as u32;
-
-        UpdatePluginV1CpiBuilder::new(&self.core_program.to_account_info())
-            .asset(&self.asset.to_account_info())
-            .collection(Some(&self.collection.to_account_info()))
-            .payer(&self.user.to_account_info())
-            .authority(Some(&self.stake_account.to_account_info()))
-            .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
-            .system_program(&self.system_program.to_account_info())
-            .invoke_signed(signer_seeds)?;
-
-        RemovePluginV1CpiBuilder::new(&self.core_program.to_account_info())
-            .asset(&self.asset.to_account_info())
-            .collection(Some(&self.collection.to_account_info()))
-            .payer(&self.user.to_account_info())
-            .authority(None)
-            .plugin_type(PluginType::FreezeDelegate)
-            .system_program(&self.system_program.to_account_info())
-            .invoke_signed(signer_seeds)?;
-
-        Ok(())
+        let points = self.stake_account.accrue(&self.config, now)?;
+        self.user_account.add_points(points)?;
+        self.stats.total_staked -= 1;
+
+        emit!(Unstaked {
+            user: self.user.key(),
+            mint: self.asset.key(),
+            points,
+            timestamp: now,
+        });
+
+        thaw_asset(
+            &self.core_program,
+            &self.asset,
+            &self.collection,
+            &self.user,
+            &self.stake_account.to_account_info(),
+            &self.system_program,
+            signer_seeds,
+        )
     }
 }
+
+/// Unfreezes the asset and removes the FreezeDelegate plugin added on stake.
+pub fn thaw_asset<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    stake_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    UpdatePluginV1CpiBuilder::new(core_program)
+        .asset(asset)
+        .collection(Some(collection))
+        .payer(owner)
+        .authority(Some(stake_account))
+        .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
+        .system_program(system_program)
+        .invoke_signed(signer_seeds)?;
+
+    RemovePluginV1CpiBuilder::new(core_program)
+        .asset(asset)
+        .collection(Some(collection))
+        .payer(owner)
+        .authority(None)
+        .plugin_type(PluginType::FreezeDelegate)
+        .system_program(system_program)
+        .invoke_signed(signer_seeds)?;
+
+    Ok(())
+}
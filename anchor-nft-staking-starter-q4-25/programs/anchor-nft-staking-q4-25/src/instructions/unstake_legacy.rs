@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        thaw_delegated_account, MasterEditionAccount, Metadata, MetadataAccount,
+        ThawDelegatedAccount,
+    },
+    token::{revoke, Mint, Revoke, Token, TokenAccount},
+};
+
+use crate::{
+    errors::StakeError,
+    events::Unstaked,
+    state::{StakeAccount, StakeConfig, StakeStats, UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct UnstakeLegacy<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+    )]
+    pub user_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub edition: Account<'info, MasterEditionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user".as_ref(), user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        close = user,
+        has_one = mint,
+        constraint = stake_account.owner == user.key() @ StakeError::NotOwner,
+        seeds = [b"stake", config.key().as_ref(), mint.key().as_ref()],
+        bump = stake_account.bump,
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UnstakeLegacy<'info> {
+    pub fn unstake_legacy(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now - self.stake_account.staked_at >= self.config.freeze_period as i64,
+            StakeError::FreezePeriodNotPassed
+        );
+
+        let points = self.stake_account.accrue(&self.config, now)?;
+        self.user_account.add_points(points)?;
+        self.user_account.amount_staked -= 1;
+        self.stats.total_staked -= 1;
+
+        emit!(Unstaked {
+            user: self.user.key(),
+            mint: self.mint.key(),
+            points,
+            timestamp: now,
+        });
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"stake",
+            &self.config.key().to_bytes(),
+            &self.mint.key().to_bytes(),
+            &[self.stake_account.bump],
+        ]];
+
+        thaw_delegated_account(CpiContext::new_with_signer(
+            self.metadata_program.to_account_info(),
+            ThawDelegatedAccount {
+                metadata: self.metadata.to_account_info(),
+                delegate: self.stake_account.to_account_info(),
+                token_account: self.user_ata.to_account_info(),
+                edition: self.edition.to_account_info(),
+                mint: self.mint.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        revoke(CpiContext::new(
+            self.token_program.to_account_info(),
+            Revoke {
+                source: self.user_ata.to_account_info(),
+                authority: self.user.to_account_info(),
+            },
+        ))
+    }
+}
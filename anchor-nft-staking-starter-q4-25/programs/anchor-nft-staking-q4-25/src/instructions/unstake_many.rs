@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use mpl_core::ID as CORE_PROGRAM_ID;
+
+use crate::{
+    errors::StakeError,
+    events::Unstaked,
+    instructions::thaw_asset,
+    state::{StakeAccount, StakeConfig, StakeStats, UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct UnstakeMany<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = collection.owner == &CORE_PROGRAM_ID @ StakeError::InvalidCollection,
+        constraint = !collection.data_is_empty() @ StakeError::CollectionNotInitialized
+    )]
+    /// CHECK: Verified by mpl-core
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user".as_ref(), user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats".as_ref(), config.key().as_ref()],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StakeStats>,
+
+    #[account(address = CORE_PROGRAM_ID)]
+    /// CHECK: Verified by address constraint
+    pub core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UnstakeMany<'info> {
+    /// Unstakes every `[asset, stake_account]` pair in the remaining accounts, all from
+    /// the same collection.
+    pub fn unstake_many(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len() % 2 == 0,
+            StakeError::InvalidStakeAccount
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        for pair in remaining_accounts.chunks(2) {
+            let (asset, stake_info) = (&pair[0], &pair[1]);
+
+            let mut stake_account = Account::<StakeAccount>::try_from(stake_info)?;
+            require_keys_eq!(stake_account.owner, self.user.key(), StakeError::NotOwner);
+            require_keys_eq!(stake_account.mint, asset.key(), StakeError::InvalidAsset);
+            require_keys_eq!(stake_account.collection, self.collection.key(), StakeError::InvalidCollection);
+            require!(
+                now - stake_account.staked_at >= self.config.freeze_period as i64,
+                StakeError::FreezePeriodNotPassed
+            );
+
+            let points = stake_account.accrue(&self.config, now)?;
+            self.user_account.add_points(points)?;
+            self.user_account.amount_staked -= 1;
+            self.stats.total_staked -= 1;
+
+            emit!(Unstaked {
+                user: self.user.key(),
+                mint: asset.key(),
+                points,
+                timestamp: now,
+            });
+
+            let config = self.config.key();
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"stake",
+                config.as_ref(),
+                asset.key.as_ref(),
+                &[stake_account.bump],
+            ]];
+
+            thaw_asset(
+                &self.core_program,
+                asset,
+                &self.collection,
+                &self.user,
+                stake_info,
+                &self.system_program,
+                signer_seeds,
+            )?;
+
+            stake_account.close(self.user.to_account_info())?;
+        }
+
+        Ok(())
+    }
+}
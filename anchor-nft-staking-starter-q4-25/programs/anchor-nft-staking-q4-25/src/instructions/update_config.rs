@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StakeConfig;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, StakeConfig>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    /// A new `points_per_stake` applies from now on, including to NFTs already staked.
+    pub fn update_config(
+        &mut self,
+        points_per_stake: u8,
+        max_stake: u8,
+        freeze_period: u32,
+    ) -> Result<()> {
+        self.config.checkpoint(Clock::get()?.unix_timestamp)?;
+
+        self.config.points_per_stake = points_per_stake;
+        self.config.max_stake = max_stake;
+        self.config.freeze_period = freeze_period;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 mod errors;
+mod events;
 mod instructions;
 mod state;
 
@@ -45,7 +46,98 @@ pub mod anchor_nft_staking_q4_25 {
         ctx.accounts.unstake()
     }
 
-    pub fn claim(ctx: Context<Claim>) -> Result<()> {
-        ctx.accounts.claim()
+    pub fn stake_legacy(ctx: Context<StakeLegacy>) -> Result<()> {
+        ctx.accounts.stake_legacy(ctx.bumps.stake_account)
+    }
+
+    pub fn unstake_legacy(ctx: Context<UnstakeLegacy>) -> Result<()> {
+        ctx.accounts.unstake_legacy()
+    }
+
+    pub fn stake_many<'info>(ctx: Context<'_, '_, 'info, 'info, StakeMany<'info>>) -> Result<()> {
+        ctx.accounts.stake_many(ctx.remaining_accounts)
+    }
+
+    pub fn unstake_many<'info>(ctx: Context<'_, '_, 'info, 'info, UnstakeMany<'info>>) -> Result<()> {
+        ctx.accounts.unstake_many(ctx.remaining_accounts)
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        points_per_stake: u8,
+        max_stake: u8,
+        freeze_period: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_config(points_per_stake, max_stake, freeze_period)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    pub fn set_collection_reward(
+        ctx: Context<SetCollectionReward>,
+        collection: Pubkey,
+        points_per_stake: u8,
+    ) -> Result<()> {
+        ctx.accounts.set_collection_reward(collection, points_per_stake)
+    }
+
+    pub fn remove_collection_reward(
+        ctx: Context<RemoveCollectionReward>,
+        collection: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.remove_collection_reward(collection)
+    }
+
+    pub fn set_legacy_collection_reward(
+        ctx: Context<SetCollectionReward>,
+        collection: Pubkey,
+        points_per_stake: u8,
+    ) -> Result<()> {
+        ctx.accounts.set_legacy_collection_reward(collection, points_per_stake)
+    }
+
+    pub fn remove_legacy_collection_reward(
+        ctx: Context<RemoveCollectionReward>,
+        collection: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.remove_legacy_collection_reward(collection)
+    }
+
+    pub fn set_attribute_multiplier(
+        ctx: Context<SetAttributeMultiplier>,
+        key: String,
+        value: String,
+        multiplier_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_attribute_multiplier(key, value, multiplier_bps)
+    }
+
+    pub fn claim<'info>(ctx: Context<'_, '_, 'info, 'info, Claim<'info>>) -> Result<()> {
+        ctx.accounts.claim(ctx.remaining_accounts)
+    }
+
+    pub fn initialize_reward_pool(
+        ctx: Context<InitializeRewardPool>,
+        emission_per_second: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_reward_pool(emission_per_second, ctx.bumps.reward_pool)
+    }
+
+    pub fn fund_reward_pool(ctx: Context<FundRewardPool>, amount: u64) -> Result<()> {
+        ctx.accounts.fund_reward_pool(amount)
+    }
+
+    pub fn set_emission_rate(ctx: Context<SetEmissionRate>, emission_per_second: u64) -> Result<()> {
+        ctx.accounts.set_emission_rate(emission_per_second)
+    }
+
+    pub fn claim_from_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimFromPool<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.claim_from_pool(ctx.remaining_accounts)
     }
 }
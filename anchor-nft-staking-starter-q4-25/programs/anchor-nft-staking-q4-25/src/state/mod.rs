@@ -9,3 +9,9 @@ pub use stake_account::*;
 
 pub mod collection_info;
 pub use collection_info::*;
+
+pub mod reward_pool;
+pub use reward_pool::*;
+
+pub mod stake_stats;
+pub use stake_stats::*;
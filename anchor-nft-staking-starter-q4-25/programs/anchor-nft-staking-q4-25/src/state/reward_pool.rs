@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StakeError;
+
+/// Emission left unclaimed stops piling up past this many seconds' worth.
+pub const MAX_BACKLOG_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Admin-funded alternative to minting rewards. Claims transfer from the pool's vault
+/// and are capped at `emission_per_second` tokens released over time.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardPool {
+    pub mint: Pubkey,
+    pub emission_per_second: u64,
+    /// Tokens released by the emission cap up to `last_update`, at most
+    /// `MAX_BACKLOG_SECONDS` of emission ahead of `emitted`.
+    pub released: u64,
+    pub emitted: u64,
+    pub last_update: i64,
+    pub bump: u8,
+}
+
+impl RewardPool {
+    /// Releases the emission for the time since the last update at the current rate.
+    /// Must run before the rate changes so past time keeps the old rate. A quiet pool
+    /// doesn't build up an unbounded backlog for a burst of claims to drain later.
+    pub fn checkpoint(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u64;
+
+        let backlog_cap = self.emission_per_second
+            .saturating_mul(MAX_BACKLOG_SECONDS)
+            .saturating_add(self.emitted);
+
+        // Anything past the cap is dropped anyway, so saturating can't lose emission
+        self.released = self.emission_per_second
+            .saturating_mul(elapsed)
+            .saturating_add(self.released)
+            .min(backlog_cap);
+        self.last_update = now;
+
+        Ok(())
+    }
+
+    pub fn record_emission(&mut self, amount: u64) -> Result<()> {
+        self.emitted = self.emitted
+            .checked_add(amount)
+            .ok_or(StakeError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Tokens the emission cap still allows out, as of the last checkpoint.
+    pub fn emittable(&self) -> u64 {
+        self.released.saturating_sub(self.emitted)
+    }
+}
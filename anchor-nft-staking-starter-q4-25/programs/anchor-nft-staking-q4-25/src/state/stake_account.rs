@@ -1,10 +1,39 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    errors::StakeError,
+    state::{StakeConfig, BPS_SCALE},
+};
+
 #[account]
 #[derive(InitSpace)]
 pub struct StakeAccount {
     pub owner: Pubkey,
     pub mint: Pubkey,
+    pub collection: Pubkey,
     pub staked_at: i64,
+    pub last_claimed: i64,
+    /// Reward index of the collection as of `last_claimed`.
+    pub reward_index: u64,
+    /// Reward multiplier from the asset's attributes when it was staked.
+    pub multiplier_bps: u16,
     pub bump: u8,
 }
+
+impl StakeAccount {
+    /// Points earned since the last accrual at the rates in effect meanwhile, moving
+    /// `last_claimed` up to `now`. Stakes of delisted collections stop earning.
+    pub fn accrue(&mut self, config: &StakeConfig, now: i64) -> Result<u64> {
+        let reward_index = config.reward_index_for(&self.collection, now)?;
+        let points = reward_index
+            .checked_sub(self.reward_index)
+            .and_then(|points| points.checked_mul(self.multiplier_bps as u64))
+            .map(|points| points / BPS_SCALE as u64)
+            .ok_or(StakeError::Overflow)?;
+
+        self.last_claimed = now;
+        self.reward_index = reward_index;
+
+        Ok(points)
+    }
+}
@@ -1,11 +1,192 @@
 use anchor_lang::prelude::*;
+use mpl_core::types::Attribute;
+
+use crate::errors::StakeError;
+
+pub const MAX_COLLECTIONS: usize = 10;
+pub const MAX_MULTIPLIERS: usize = 8;
+
+/// Multipliers are in basis points, so `BPS_SCALE` earns the base rate.
+pub const BPS_SCALE: u16 = 10_000;
 
 #[account]
 #[derive(InitSpace)]
 pub struct StakeConfig {
+    pub admin: Pubkey,
     pub points_per_stake: u8,
+    /// Points per stake earned at the base rate up to `index_updated_at`. Stakes
+    /// accrue the difference since their last accrual, so rate changes aren't retroactive.
+    pub reward_index: u64,
+    pub index_updated_at: i64,
     pub max_stake: u8,
     pub freeze_period: u32,
+    pub paused: bool,
+    /// Whitelisted collections and their reward rates. While none is listed, every
+    /// registered collection without an entry is eligible at `points_per_stake`.
+    #[max_len(MAX_COLLECTIONS)]
+    pub collections: Vec<CollectionReward>,
+    /// Legacy Token Metadata collections and their reward rates. Kept apart from
+    /// `collections` so listing one doesn't close staking to unlisted core collections.
+    #[max_len(MAX_COLLECTIONS)]
+    pub legacy_collections: Vec<CollectionReward>,
+    /// Reward multipliers for assets carrying a given attribute.
+    #[max_len(MAX_MULTIPLIERS)]
+    pub multipliers: Vec<AttributeMultiplier>,
+    /// Set once a reward pool is initialized; `claim` then stops minting and rewards
+    /// are paid from the pool instead.
+    pub reward_pool: Option<Pubkey>,
     pub rewards_bump: u8,
     pub bump: u8,
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CollectionReward {
+    pub collection: Pubkey,
+    pub points_per_stake: u8,
+    /// Delisted collections keep their entry, earning nothing, so their stakes keep
+    /// what they earned before.
+    pub listed: bool,
+    /// Points per stake earned by the collection up to `index_updated_at`.
+    pub reward_index: u64,
+    pub index_updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct AttributeMultiplier {
+    #[max_len(32)]
+    pub key: String,
+    #[max_len(64)]
+    pub value: String,
+    pub multiplier_bps: u16,
+}
+
+/// `index` brought forward from `updated_at` to `now` at `rate` points per second.
+fn index_at(index: u64, rate: u64, updated_at: i64, now: i64) -> Result<u64> {
+    let elapsed = u64::try_from(now.saturating_sub(updated_at)).unwrap_or(0);
+
+    rate.checked_mul(elapsed)
+        .and_then(|points| points.checked_add(index))
+        .ok_or(StakeError::Overflow.into())
+}
+
+/// Lists `collection` in `rewards` at `points_per_stake`, or updates its rate. New
+/// entries start at `reward_index`.
+pub fn list_collection(
+    rewards: &mut Vec<CollectionReward>,
+    collection: Pubkey,
+    points_per_stake: u8,
+    reward_index: u64,
+    now: i64,
+) -> Result<()> {
+    if let Some(reward) = rewards.iter_mut().find(|reward| reward.collection == collection) {
+        reward.points_per_stake = points_per_stake;
+        reward.listed = true;
+        return Ok(());
+    }
+
+    require!(rewards.len() < MAX_COLLECTIONS, StakeError::CollectionWhitelistFull);
+    rewards.push(CollectionReward {
+        collection,
+        points_per_stake,
+        listed: true,
+        reward_index,
+        index_updated_at: now,
+    });
+
+    Ok(())
+}
+
+/// Delists `collection` from `rewards`, keeping its entry.
+pub fn delist_collection(rewards: &mut [CollectionReward], collection: Pubkey) -> Result<()> {
+    let reward = rewards
+        .iter_mut()
+        .find(|reward| reward.collection == collection && reward.listed)
+        .ok_or(StakeError::CollectionNotWhitelisted)?;
+
+    reward.listed = false;
+
+    Ok(())
+}
+
+impl CollectionReward {
+    pub fn reward_index_at(&self, now: i64) -> Result<u64> {
+        let rate = if self.listed { self.points_per_stake as u64 } else { 0 };
+
+        index_at(self.reward_index, rate, self.index_updated_at, now)
+    }
+
+    pub fn checkpoint(&mut self, now: i64) -> Result<()> {
+        self.reward_index = self.reward_index_at(now)?;
+        self.index_updated_at = now;
+
+        Ok(())
+    }
+}
+
+impl StakeConfig {
+    /// Whether staking is limited to listed collections.
+    pub fn whitelist_in_use(&self) -> bool {
+        self.collections.iter().any(|reward| reward.listed)
+    }
+
+    /// `reward_index` brought forward to `now`. The base rate only runs while the
+    /// whitelist is not in use.
+    pub fn reward_index_at(&self, now: i64) -> Result<u64> {
+        let rate = if self.whitelist_in_use() { 0 } else { self.points_per_stake as u64 };
+
+        index_at(self.reward_index, rate, self.index_updated_at, now)
+    }
+
+    /// Reward index stakes of `collection` accrue against: its entry's if it has
+    /// one, the base one otherwise. Legacy collections always have an entry.
+    pub fn reward_index_for(&self, collection: &Pubkey, now: i64) -> Result<u64> {
+        match self.collection_reward(collection).or(self.legacy_collection_reward(collection)) {
+            Some(reward) => reward.reward_index_at(now),
+            None => self.reward_index_at(now),
+        }
+    }
+
+    /// Records what every rate earned so far. Must run before any rate or the
+    /// whitelist changes, so past time keeps the old rates.
+    pub fn checkpoint(&mut self, now: i64) -> Result<()> {
+        self.reward_index = self.reward_index_at(now)?;
+        self.index_updated_at = now;
+
+        for reward in self.collections.iter_mut().chain(self.legacy_collections.iter_mut()) {
+            reward.checkpoint(now)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn collection_reward(&self, collection: &Pubkey) -> Option<&CollectionReward> {
+        self.collections.iter().find(|reward| reward.collection == *collection)
+    }
+
+    pub fn legacy_collection_reward(&self, collection: &Pubkey) -> Option<&CollectionReward> {
+        self.legacy_collections.iter().find(|reward| reward.collection == *collection)
+    }
+
+    /// Reward rate of `collection`, or `None` if it is not eligible for staking.
+    /// Delisted collections stay ineligible even once the whitelist is unused again.
+    pub fn points_per_stake_for(&self, collection: &Pubkey) -> Option<u8> {
+        match self.collection_reward(collection) {
+            Some(reward) => reward.listed.then_some(reward.points_per_stake),
+            None => (!self.whitelist_in_use()).then_some(self.points_per_stake),
+        }
+    }
+
+    /// Highest multiplier matching one of `attributes`, or the base rate if none does.
+    pub fn multiplier_bps_for(&self, attributes: &[Attribute]) -> u16 {
+        self.multipliers
+            .iter()
+            .filter(|multiplier| {
+                attributes
+                    .iter()
+                    .any(|attribute| attribute.key == multiplier.key && attribute.value == multiplier.value)
+            })
+            .map(|multiplier| multiplier.multiplier_bps)
+            .max()
+            .unwrap_or(BPS_SCALE)
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StakeError;
+
+/// Program-wide totals across every user.
+#[account]
+#[derive(InitSpace)]
+pub struct StakeStats {
+    /// Assets currently staked.
+    pub total_staked: u64,
+    pub total_emitted: u64,
+    pub bump: u8,
+}
+
+impl StakeStats {
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        self.total_emitted = self.total_emitted
+            .checked_add(amount)
+            .ok_or(StakeError::Overflow)?;
+
+        Ok(())
+    }
+}
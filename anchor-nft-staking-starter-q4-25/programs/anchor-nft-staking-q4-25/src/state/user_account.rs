@@ -1,9 +1,47 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::StakeError;
+
 #[account]
 #[derive(InitSpace)]
 pub struct UserAccount {
-    pub points: u32,
+    pub points: u64,
     pub amount_staked: u8,
+    /// Lifetime points earned, claimed or not.
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    /// Time of the user's first stake, or 0 if they never staked.
+    pub first_staked_at: i64,
     pub bump: u8,
 }
+
+impl UserAccount {
+    pub fn add_points(&mut self, points: u64) -> Result<()> {
+        self.points = self.points
+            .checked_add(points)
+            .ok_or(StakeError::Overflow)?;
+        self.total_earned = self.total_earned
+            .checked_add(points)
+            .ok_or(StakeError::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn record_stake(&mut self, now: i64) {
+        if self.first_staked_at == 0 {
+            self.first_staked_at = now;
+        }
+    }
+
+    /// Moves `amount` claimed points out of the unclaimed balance.
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        self.points = self.points
+            .checked_sub(amount)
+            .ok_or(StakeError::Overflow)?;
+        self.total_claimed = self.total_claimed
+            .checked_add(amount)
+            .ok_or(StakeError::Overflow)?;
+
+        Ok(())
+    }
+}
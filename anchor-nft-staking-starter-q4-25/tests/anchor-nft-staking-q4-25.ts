@@ -1,11 +1,135 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { AnchorNftStakingQ425 } from "../target/types/anchor_nft_staking_q4_25";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
-import { getAssociatedTokenAddressSync } from "@solana/spl-token";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 import { MPL_CORE_PROGRAM_ID } from "@metaplex-foundation/mpl-core";
 import { assert } from "chai";
 
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bBrU2J7Gp"
+);
+
+const borshString = (value: string) => {
+  const bytes = Buffer.from(value, "utf8");
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(bytes.length);
+  return Buffer.concat([len, bytes]);
+};
+
+const metadataPda = (mint: PublicKey) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  )[0];
+
+const editionPda = (mint: PublicKey) =>
+  PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("edition"),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  )[0];
+
+// Mints a Token Metadata master edition NFT to `owner`, who is also its authority,
+// optionally as an unverified member of `collection`.
+async function createLegacyNft(
+  provider: anchor.AnchorProvider,
+  owner: Keypair,
+  collection?: PublicKey
+): Promise<PublicKey> {
+  const connection = provider.connection;
+  const mint = await createMint(connection, owner, owner.publicKey, owner.publicKey, 0);
+  const ata = await getOrCreateAssociatedTokenAccount(connection, owner, mint, owner.publicKey);
+  await mintTo(connection, owner, mint, ata.address, owner, 1);
+
+  // CreateMetadataAccountV3 with DataV2 and no creators, uses or collection details
+  const createMetadata = new TransactionInstruction({
+    programId: TOKEN_METADATA_PROGRAM_ID,
+    keys: [
+      { pubkey: metadataPda(mint), isSigner: false, isWritable: true },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: true },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    data: Buffer.concat([
+      Buffer.from([33]),
+      borshString("Legacy NFT"),
+      borshString("LGCY"),
+      borshString("https://example.com/legacy.json"),
+      Buffer.from([0, 0, 0]),
+      collection
+        ? Buffer.concat([Buffer.from([1, 0]), collection.toBuffer()])
+        : Buffer.from([0]),
+      Buffer.from([0, 1, 0]),
+    ]),
+  });
+
+  // CreateMasterEditionV3 with a max supply of zero
+  const createMasterEdition = new TransactionInstruction({
+    programId: TOKEN_METADATA_PROGRAM_ID,
+    keys: [
+      { pubkey: editionPda(mint), isSigner: false, isWritable: true },
+      { pubkey: mint, isSigner: false, isWritable: true },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+      { pubkey: owner.publicKey, isSigner: true, isWritable: true },
+      { pubkey: metadataPda(mint), isSigner: false, isWritable: true },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    data: Buffer.concat([Buffer.from([17, 1]), Buffer.alloc(8)]),
+  });
+
+  await provider.sendAndConfirm(
+    new Transaction().add(createMetadata, createMasterEdition),
+    [owner]
+  );
+
+  return mint;
+}
+
+// Verifies `mint` as a member of the unsized legacy `collection`.
+async function verifyLegacyCollection(
+  provider: anchor.AnchorProvider,
+  authority: Keypair,
+  mint: PublicKey,
+  collection: PublicKey
+) {
+  const verifyCollection = new TransactionInstruction({
+    programId: TOKEN_METADATA_PROGRAM_ID,
+    keys: [
+      { pubkey: metadataPda(mint), isSigner: false, isWritable: true },
+      { pubkey: authority.publicKey, isSigner: true, isWritable: true },
+      { pubkey: authority.publicKey, isSigner: true, isWritable: true },
+      { pubkey: collection, isSigner: false, isWritable: false },
+      { pubkey: metadataPda(collection), isSigner: false, isWritable: false },
+      { pubkey: editionPda(collection), isSigner: false, isWritable: false },
+    ],
+    data: Buffer.from([18]),
+  });
+
+  await provider.sendAndConfirm(new Transaction().add(verifyCollection), [authority]);
+}
+
 describe("anchor-nft-staking-q4-25", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -28,11 +152,25 @@ describe("anchor-nft-staking-q4-25", () => {
   // PDAs
   let configPda: PublicKey;
   let rewardMintPda: PublicKey;
+  let statsPda: PublicKey;
   let userAccountPda: PublicKey;
   let collectionInfoPda: PublicKey;
   let stakeAccountPda: PublicKey;
   let rewardsAtaPda: PublicKey;
 
+  // Events the program emitted in the confirmed transaction `signature`
+  const eventsOf = async (signature: string, name: string) => {
+    await connection.confirmTransaction(signature, "confirmed");
+    const tx = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    return Array.from(parser.parseLogs(tx.meta.logMessages)).filter(
+      (event) => event.name.toLowerCase() === name.toLowerCase()
+    );
+  };
+
   console.log(`Admin: ${admin.publicKey.toString()}`);
   console.log(`User: ${user.publicKey.toString()}`);
   console.log(`Collection: ${collection.publicKey.toString()}`);
@@ -56,6 +194,12 @@ describe("anchor-nft-staking-q4-25", () => {
     )[0];
     console.log(`Reward Mint PDA: ${rewardMintPda.toString()}`);
 
+    statsPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), configPda.toBuffer()],
+      program.programId
+    )[0];
+    console.log(`Stats PDA: ${statsPda.toString()}`);
+
     userAccountPda = PublicKey.findProgramAddressSync(
       [Buffer.from("user"), user.publicKey.toBuffer()],
       program.programId
@@ -88,6 +232,7 @@ describe("anchor-nft-staking-q4-25", () => {
         .accountsStrict({
           admin: admin.publicKey,
           config: configPda,
+          stats: statsPda,
           rewardMint: rewardMintPda,
           tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -100,8 +245,56 @@ describe("anchor-nft-staking-q4-25", () => {
       assert.equal(config.pointsPerStake, pointsPerStake);
       assert.equal(config.maxStake, maxStake);
       assert.equal(config.freezePeriod, freezePeriod);
+      assert.equal(config.admin.toString(), admin.publicKey.toString());
+      assert.isFalse(config.paused);
       console.log("Config initialized successfully");
     });
+
+    it("Update the staking config as admin", async () => {
+      const before = await program.account.stakeConfig.fetch(configPda);
+
+      await program.methods
+        .updateConfig(pointsPerStake, maxStake + 1, freezePeriod)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      let config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.maxStake, maxStake + 1);
+
+      // The base rate is checkpointed before the update, so it only applies from now on
+      const elapsed = config.indexUpdatedAt.sub(before.indexUpdatedAt);
+      assert.ok(config.rewardIndex.eq(before.rewardIndex.add(elapsed.muln(pointsPerStake))));
+
+      await program.methods
+        .updateConfig(pointsPerStake, maxStake, freezePeriod)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.maxStake, maxStake);
+    });
+
+    it("Reject config updates from anyone but the admin", async () => {
+      try {
+        await program.methods
+          .updateConfig(255, 255, 0)
+          .accountsStrict({
+            admin: user.publicKey,
+            config: configPda,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Update should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+      }
+    });
   });
 
   describe("Initialize User", () => {
@@ -121,7 +314,7 @@ describe("anchor-nft-staking-q4-25", () => {
       const userAccount = await program.account.userAccount.fetch(
         userAccountPda
       );
-      assert.equal(userAccount.points, 0);
+      assert.equal(userAccount.points.toNumber(), 0);
       assert.equal(userAccount.amountStaked, 0);
       console.log("User account initialized successfully");
     });
@@ -165,6 +358,42 @@ describe("anchor-nft-staking-q4-25", () => {
       assert.equal(collectionInfo.nftName, args.nftName);
       console.log("Collection created successfully");
     });
+
+    it("Whitelist the collection with its own reward rate", async () => {
+      await program.methods
+        .setCollectionReward(collection.publicKey, pointsPerStake)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      const config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.collections.length, 1);
+      assert.equal(
+        config.collections[0].collection.toString(),
+        collection.publicKey.toString()
+      );
+      assert.equal(config.collections[0].pointsPerStake, pointsPerStake);
+      assert.isTrue(config.collections[0].listed);
+      // Stakes made at the base rate carry on from the base index
+      assert.ok(config.collections[0].rewardIndex.eq(config.rewardIndex));
+    });
+
+    it("Set a 3x multiplier for assets minted by the user", async () => {
+      // mint_nft writes a Minter attribute holding the minter's address
+      await program.methods
+        .setAttributeMultiplier("Minter", user.publicKey.toString(), 30_000)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      const config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.multipliers.length, 1);
+      assert.equal(config.multipliers[0].multiplierBps, 30_000);
+    });
   });
 
   describe("Mint NFT", () => {
@@ -192,6 +421,135 @@ describe("anchor-nft-staking-q4-25", () => {
   });
 
   describe("Stake NFT", () => {
+    it("Refuse to stake while paused", async () => {
+      await program.methods
+        .setPaused(true)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .stake()
+          .accountsStrict({
+            user: user.publicKey,
+            asset: asset.publicKey,
+            collection: collection.publicKey,
+            collectionInfo: collectionInfoPda,
+            stakeAccount: stakeAccountPda,
+            config: configPda,
+            stats: statsPda,
+            userAccount: userAccountPda,
+            coreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Stake should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "StakingPaused");
+      }
+
+      await program.methods
+        .setPaused(false)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+    });
+
+    it("Refuse to stake an asset from a collection that isn't whitelisted", async () => {
+      const otherCollection = Keypair.generate();
+      const otherAsset = Keypair.generate();
+      const otherCollectionInfoPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("collection_info"), otherCollection.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+      await program.methods
+        .createCollection({
+          name: "Other Collection",
+          uri: "https://example.com/other.json",
+          nftName: "Other NFT",
+          nftUri: "https://example.com/other-nft.json",
+        })
+        .accountsStrict({
+          authority: admin.publicKey,
+          collection: otherCollection.publicKey,
+          collectionInfo: otherCollectionInfoPda,
+          coreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([otherCollection])
+        .rpc();
+
+      await program.methods
+        .mintNft()
+        .accountsStrict({
+          minter: user.publicKey,
+          asset: otherAsset.publicKey,
+          collection: otherCollection.publicKey,
+          collectionInfo: otherCollectionInfoPda,
+          coreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user, otherAsset])
+        .rpc();
+
+      const otherStakeAccountPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake"), configPda.toBuffer(), otherAsset.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+      try {
+        await program.methods
+          .stake()
+          .accountsStrict({
+            user: user.publicKey,
+            asset: otherAsset.publicKey,
+            collection: otherCollection.publicKey,
+            collectionInfo: otherCollectionInfoPda,
+            stakeAccount: otherStakeAccountPda,
+            config: configPda,
+            stats: statsPda,
+            userAccount: userAccountPda,
+            coreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Stake should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "CollectionNotWhitelisted");
+      }
+
+      // Nor can it be passed off as a member of the whitelisted collection
+      try {
+        await program.methods
+          .stake()
+          .accountsStrict({
+            user: user.publicKey,
+            asset: otherAsset.publicKey,
+            collection: collection.publicKey,
+            collectionInfo: collectionInfoPda,
+            stakeAccount: otherStakeAccountPda,
+            config: configPda,
+            stats: statsPda,
+            userAccount: userAccountPda,
+            coreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Stake should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "InvalidCollection");
+      }
+    });
+
     it("Stake the NFT and add FreezeDelegate plugin", async () => {
       const userAccountBefore = await program.account.userAccount.fetch(
         userAccountPda
@@ -204,8 +562,10 @@ describe("anchor-nft-staking-q4-25", () => {
           user: user.publicKey,
           asset: asset.publicKey,
           collection: collection.publicKey,
+          collectionInfo: collectionInfoPda,
           stakeAccount: stakeAccountPda,
           config: configPda,
+          stats: statsPda,
           userAccount: userAccountPda,
           coreProgram: MPL_CORE_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -220,16 +580,143 @@ describe("anchor-nft-staking-q4-25", () => {
       );
       assert.equal(stakeAccount.owner.toString(), user.publicKey.toString());
       assert.equal(stakeAccount.mint.toString(), asset.publicKey.toString());
+      assert.equal(
+        stakeAccount.collection.toString(),
+        collection.publicKey.toString()
+      );
+      assert.equal(stakeAccount.multiplierBps, 30_000);
       assert.ok(stakeAccount.stakedAt > new anchor.BN(0));
 
       const userAccountAfter = await program.account.userAccount.fetch(
         userAccountPda
       );
       assert.equal(userAccountAfter.amountStaked, 1);
+      assert.ok(userAccountAfter.firstStakedAt.eq(stakeAccount.stakedAt));
+      assert.ok(stakeAccount.lastClaimed.eq(stakeAccount.stakedAt));
+
+      const stats = await program.account.stakeStats.fetch(statsPda);
+      assert.equal(stats.totalStaked.toNumber(), 1);
+
+      const [staked] = await eventsOf(tx, "Staked");
+      assert.equal(staked.data.user.toString(), user.publicKey.toString());
+      assert.equal(staked.data.mint.toString(), asset.publicKey.toString());
+      assert.equal(staked.data.collection.toString(), collection.publicKey.toString());
+      assert.ok(staked.data.timestamp.eq(stakeAccount.stakedAt));
       console.log("NFT staked successfully");
     });
   });
 
+  describe("Claim While Staked", () => {
+    it("Claim points accrued by a staked NFT without unstaking", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      const tx = await program.methods
+        .claim()
+        .accountsStrict({
+          user: user.publicKey,
+          rewardsAta: rewardsAtaPda,
+          config: configPda,
+          stats: statsPda,
+          userAccount: userAccountPda,
+          rewardMint: rewardMintPda,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: stakeAccountPda, isSigner: false, isWritable: true },
+        ])
+        .signers([user])
+        .rpc();
+
+      console.log(`Claim While Staked tx: ${tx}`);
+
+      const stakeAccount = await program.account.stakeAccount.fetch(
+        stakeAccountPda
+      );
+      assert.ok(stakeAccount.lastClaimed.gt(stakeAccount.stakedAt));
+
+      const userAccount = await program.account.userAccount.fetch(
+        userAccountPda
+      );
+      assert.equal(userAccount.points.toNumber(), 0);
+      assert.equal(userAccount.amountStaked, 1);
+
+      const rewardsBalance = await connection.getTokenAccountBalance(rewardsAtaPda);
+      // 3x multiplier from the Minter attribute
+      assert.ok(Number(rewardsBalance.value.amount) >= 2 * pointsPerStake * 3);
+
+      const [claimed] = await eventsOf(tx, "Claimed");
+      assert.equal(claimed.data.user.toString(), user.publicKey.toString());
+      assert.equal(claimed.data.amount.toString(), rewardsBalance.value.amount);
+      console.log(`Claimed ${rewardsBalance.value.amount} while staked`);
+    });
+
+    it("Apply a collection rate change only from the time it is made", async () => {
+      const newRate = pointsPerStake * 10;
+      const before = await program.account.stakeConfig.fetch(configPda);
+      const stakeBefore = await program.account.stakeAccount.fetch(stakeAccountPda);
+
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      await program.methods
+        .setCollectionReward(collection.publicKey, newRate)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      // Time up to the change is checkpointed at the old rate
+      const changed = await program.account.stakeConfig.fetch(configPda);
+      const [entryBefore, entry] = [before.collections[0], changed.collections[0]];
+      assert.equal(entry.pointsPerStake, newRate);
+      const elapsedAtOldRate = entry.indexUpdatedAt.sub(entryBefore.indexUpdatedAt);
+      assert.ok(entry.rewardIndex.eq(entryBefore.rewardIndex.add(elapsedAtOldRate.muln(pointsPerStake))));
+
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+
+      const tx = await program.methods
+        .claim()
+        .accountsStrict({
+          user: user.publicKey,
+          rewardsAta: rewardsAtaPda,
+          config: configPda,
+          stats: statsPda,
+          userAccount: userAccountPda,
+          rewardMint: rewardMintPda,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: stakeAccountPda, isSigner: false, isWritable: true },
+        ])
+        .signers([user])
+        .rpc();
+
+      const stakeAfter = await program.account.stakeAccount.fetch(stakeAccountPda);
+      const elapsedAtNewRate = stakeAfter.lastClaimed.sub(entry.indexUpdatedAt);
+      const expected = entry.rewardIndex
+        .sub(stakeBefore.rewardIndex)
+        .add(elapsedAtNewRate.muln(newRate))
+        .muln(3);
+      const retroactive = stakeAfter.lastClaimed.sub(stakeBefore.lastClaimed).muln(newRate * 3);
+
+      const [claimed] = await eventsOf(tx, "Claimed");
+      assert.ok(claimed.data.amount.eq(expected));
+      assert.ok(claimed.data.amount.lt(retroactive));
+
+      await program.methods
+        .setCollectionReward(collection.publicKey, pointsPerStake)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+    });
+  });
+
   describe("Unstake NFT", () => {
     it("Unstake the NFT and remove FreezeDelegate plugin", async () => {
       // Wait for freeze period if needed (0 in this test)
@@ -242,7 +729,7 @@ describe("anchor-nft-staking-q4-25", () => {
       const userAccountBefore = await program.account.userAccount.fetch(
         userAccountPda
       );
-      const pointsBefore = userAccountBefore.points;
+      const pointsBefore = userAccountBefore.points.toNumber();
 
       const tx = await program.methods
         .unstake()
@@ -252,6 +739,7 @@ describe("anchor-nft-staking-q4-25", () => {
           collection: collection.publicKey,
           stakeAccount: stakeAccountPda,
           config: configPda,
+          stats: statsPda,
           userAccount: userAccountPda,
           coreProgram: MPL_CORE_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -274,11 +762,23 @@ describe("anchor-nft-staking-q4-25", () => {
         userAccountPda
       );
       assert.equal(userAccountAfter.amountStaked, 0);
-      // Points should be awarded (time_elapsed * points_per_stake)
-      assert.ok(userAccountAfter.points >= pointsBefore);
+      assert.equal(
+        (await program.account.stakeStats.fetch(statsPda)).totalStaked.toNumber(),
+        0
+      );
+      // Points should be awarded (time since last claim * points_per_stake)
+      assert.ok(userAccountAfter.points.toNumber() >= pointsBefore);
+
+      const [unstaked] = await eventsOf(tx, "Unstaked");
+      assert.equal(unstaked.data.user.toString(), user.publicKey.toString());
+      assert.equal(unstaked.data.mint.toString(), asset.publicKey.toString());
+      assert.equal(
+        unstaked.data.points.toNumber(),
+        userAccountAfter.points.toNumber() - pointsBefore
+      );
       console.log(
         `NFT unstaked successfully, points earned: ${
-          userAccountAfter.points - pointsBefore
+          userAccountAfter.points.toNumber() - pointsBefore
         }`
       );
     });
@@ -289,7 +789,7 @@ describe("anchor-nft-staking-q4-25", () => {
       const userAccountBefore = await program.account.userAccount.fetch(
         userAccountPda
       );
-      const pointsBefore = userAccountBefore.points;
+      const pointsBefore = userAccountBefore.points.toNumber();
 
       if (pointsBefore === 0) {
         console.log("No points to claim, skipping claim test");
@@ -302,6 +802,7 @@ describe("anchor-nft-staking-q4-25", () => {
           user: user.publicKey,
           rewardsAta: rewardsAtaPda,
           config: configPda,
+          stats: statsPda,
           userAccount: userAccountPda,
           rewardMint: rewardMintPda,
           associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
@@ -316,7 +817,15 @@ describe("anchor-nft-staking-q4-25", () => {
       const userAccountAfter = await program.account.userAccount.fetch(
         userAccountPda
       );
-      assert.equal(userAccountAfter.points, 0, "Points should be reset to 0");
+      assert.equal(userAccountAfter.points.toNumber(), 0, "Points should be reset to 0");
+      assert.equal(
+        userAccountAfter.totalClaimed.toNumber() - userAccountBefore.totalClaimed.toNumber(),
+        pointsBefore
+      );
+      assert.ok(userAccountAfter.totalEarned.gte(userAccountAfter.totalClaimed));
+
+      const stats = await program.account.stakeStats.fetch(statsPda);
+      assert.equal(stats.totalEmitted.toNumber(), userAccountAfter.totalClaimed.toNumber());
 
       const rewardsBalanceAfter = await connection.getTokenAccountBalance(rewardsAtaPda);
       assert.notEqual(rewardsBalanceAfter.value.uiAmount, 0, "Rewards balance should not be 0");
@@ -327,4 +836,389 @@ describe("anchor-nft-staking-q4-25", () => {
       console.log("Rewards claimed successfully");
     });
   });
+
+  describe("Batch Stake", () => {
+    const assets = [Keypair.generate(), Keypair.generate()];
+    const stakeAccounts = () =>
+      assets.map(
+        (a) =>
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("stake"), configPda.toBuffer(), a.publicKey.toBuffer()],
+            program.programId
+          )[0]
+      );
+    const pairs = () =>
+      assets.flatMap((a, i) => [
+        { pubkey: a.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakeAccounts()[i], isSigner: false, isWritable: true },
+      ]);
+
+    before(async () => {
+      for (const a of assets) {
+        await program.methods
+          .mintNft()
+          .accountsStrict({
+            minter: user.publicKey,
+            asset: a.publicKey,
+            collection: collection.publicKey,
+            collectionInfo: collectionInfoPda,
+            coreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user, a])
+          .rpc();
+      }
+    });
+
+    const updateMaxStake = (max: number) =>
+      program.methods
+        .updateConfig(pointsPerStake, max, freezePeriod)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+    it("Refuse a batch that would exceed max_stake", async () => {
+      await updateMaxStake(assets.length - 1);
+
+      try {
+        await program.methods
+          .stakeMany()
+          .accountsStrict({
+            user: user.publicKey,
+            collection: collection.publicKey,
+            collectionInfo: collectionInfoPda,
+            userAccount: userAccountPda,
+            config: configPda,
+            stats: statsPda,
+            coreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(pairs())
+          .signers([user])
+          .rpc();
+        assert.fail("Batch stake should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "MaxStakeReached");
+      } finally {
+        await updateMaxStake(maxStake);
+      }
+    });
+
+    it("Stake several NFTs in one transaction", async () => {
+      // Prefunding a stake account address must not block the stake
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: admin.publicKey,
+            toPubkey: stakeAccounts()[0],
+            lamports: 1_000_000,
+          })
+        )
+      );
+
+      await program.methods
+        .stakeMany()
+        .accountsStrict({
+          user: user.publicKey,
+          collection: collection.publicKey,
+          collectionInfo: collectionInfoPda,
+          userAccount: userAccountPda,
+          config: configPda,
+          stats: statsPda,
+          coreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(pairs())
+        .signers([user])
+        .rpc();
+
+      const userAccount = await program.account.userAccount.fetch(
+        userAccountPda
+      );
+      assert.equal(userAccount.amountStaked, assets.length);
+
+      for (const [i, stakeAccountPda] of stakeAccounts().entries()) {
+        const stakeAccount = await program.account.stakeAccount.fetch(
+          stakeAccountPda
+        );
+        assert.equal(
+          stakeAccount.mint.toString(),
+          assets[i].publicKey.toString()
+        );
+      }
+    });
+
+    it("Unstake several NFTs in one transaction", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (freezePeriod + 1) * 1000));
+
+      await program.methods
+        .unstakeMany()
+        .accountsStrict({
+          user: user.publicKey,
+          collection: collection.publicKey,
+          userAccount: userAccountPda,
+          config: configPda,
+          stats: statsPda,
+          coreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(pairs())
+        .signers([user])
+        .rpc();
+
+      const userAccount = await program.account.userAccount.fetch(
+        userAccountPda
+      );
+      assert.equal(userAccount.amountStaked, 0);
+      assert.ok(userAccount.points.toNumber() > 0);
+
+      for (const stakeAccountPda of stakeAccounts()) {
+        assert.isNull(await connection.getAccountInfo(stakeAccountPda));
+      }
+    });
+  });
+
+  describe("Legacy Stake", () => {
+    let legacyCollection: PublicKey;
+    let legacyMint: PublicKey;
+    let legacyAta: PublicKey;
+    let legacyStakeAccount: PublicKey;
+
+    const legacyAccounts = () => ({
+      user: user.publicKey,
+      mint: legacyMint,
+      userAta: legacyAta,
+      metadata: metadataPda(legacyMint),
+      edition: editionPda(legacyMint),
+      userAccount: userAccountPda,
+      stakeAccount: legacyStakeAccount,
+      config: configPda,
+      stats: statsPda,
+      metadataProgram: TOKEN_METADATA_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      legacyCollection = await createLegacyNft(provider, user);
+      legacyMint = await createLegacyNft(provider, user, legacyCollection);
+      await verifyLegacyCollection(provider, user, legacyMint, legacyCollection);
+
+      legacyAta = getAssociatedTokenAddressSync(legacyMint, user.publicKey);
+      legacyStakeAccount = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake"), configPda.toBuffer(), legacyMint.toBuffer()],
+        program.programId
+      )[0];
+    });
+
+    it("Whitelist a legacy collection apart from core collections", async () => {
+      const before = await program.account.stakeConfig.fetch(configPda);
+
+      await program.methods
+        .setLegacyCollectionReward(legacyCollection, pointsPerStake)
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+        })
+        .rpc();
+
+      const config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.legacyCollections.length, 1);
+      assert.equal(
+        config.legacyCollections[0].collection.toString(),
+        legacyCollection.toString()
+      );
+      // The core whitelist is untouched
+      assert.equal(config.collections.length, before.collections.length);
+    });
+
+    it("Stake a legacy NFT by freezing it in the user's wallet", async () => {
+      await program.methods
+        .stakeLegacy()
+        .accountsStrict(legacyAccounts())
+        .signers([user])
+        .rpc();
+
+      const tokenAccount = await getAccount(connection, legacyAta);
+      assert.equal(Number(tokenAccount.amount), 1);
+      assert.isTrue(tokenAccount.isFrozen);
+      assert.equal(tokenAccount.delegate.toString(), legacyStakeAccount.toString());
+
+      const stakeAccount = await program.account.stakeAccount.fetch(legacyStakeAccount);
+      assert.equal(stakeAccount.collection.toString(), legacyCollection.toString());
+      assert.equal(
+        (await program.account.userAccount.fetch(userAccountPda)).amountStaked,
+        1
+      );
+    });
+
+    it("Unstake the legacy NFT by thawing it", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (freezePeriod + 1) * 1000));
+      const pointsBefore = (await program.account.userAccount.fetch(userAccountPda)).points;
+
+      await program.methods
+        .unstakeLegacy()
+        .accountsStrict(legacyAccounts())
+        .signers([user])
+        .rpc();
+
+      const tokenAccount = await getAccount(connection, legacyAta);
+      assert.isFalse(tokenAccount.isFrozen);
+      assert.isNull(tokenAccount.delegate);
+      assert.isNull(await connection.getAccountInfo(legacyStakeAccount));
+
+      const userAccount = await program.account.userAccount.fetch(userAccountPda);
+      assert.equal(userAccount.amountStaked, 0);
+      assert.ok(userAccount.points.gt(pointsBefore));
+    });
+  });
+
+  describe("Reward Pool", () => {
+    const fundAmount = 1_000;
+    let rewardToken: PublicKey;
+    let rewardPoolPda: PublicKey;
+    let vault: PublicKey;
+    let userRewardsAta: PublicKey;
+
+    const claimFromPool = () =>
+      program.methods
+        .claimFromPool()
+        .accountsStrict({
+          user: user.publicKey,
+          rewardsAta: userRewardsAta,
+          userAccount: userAccountPda,
+          config: configPda,
+          stats: statsPda,
+          rewardPool: rewardPoolPda,
+          rewardToken,
+          vault,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    before(async () => {
+      const payer = (admin as anchor.Wallet).payer;
+      rewardToken = await createMint(connection, payer, admin.publicKey, null, 6);
+      const adminAta = await getOrCreateAssociatedTokenAccount(
+        connection,
+        payer,
+        rewardToken,
+        admin.publicKey
+      );
+      await mintTo(connection, payer, rewardToken, adminAta.address, payer, fundAmount);
+
+      rewardPoolPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), configPda.toBuffer()],
+        program.programId
+      )[0];
+      vault = getAssociatedTokenAddressSync(rewardToken, rewardPoolPda, true);
+      userRewardsAta = getAssociatedTokenAddressSync(rewardToken, user.publicKey);
+    });
+
+    it("Initialize and fund the reward pool", async () => {
+      await program.methods
+        .initializeRewardPool(new anchor.BN(0))
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+          rewardPool: rewardPoolPda,
+          rewardToken,
+          vault,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .fundRewardPool(new anchor.BN(fundAmount))
+        .accountsStrict({
+          admin: admin.publicKey,
+          adminAta: getAssociatedTokenAddressSync(rewardToken, admin.publicKey),
+          config: configPda,
+          rewardPool: rewardPoolPda,
+          rewardToken,
+          vault,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const config = await program.account.stakeConfig.fetch(configPda);
+      assert.equal(config.rewardPool.toString(), rewardPoolPda.toString());
+
+      const vaultBalance = await connection.getTokenAccountBalance(vault);
+      assert.equal(vaultBalance.value.amount, fundAmount.toString());
+    });
+
+    it("Stop minting rewards once the pool is enabled", async () => {
+      try {
+        await program.methods
+          .claim()
+          .accountsStrict({
+            user: user.publicKey,
+            rewardsAta: rewardsAtaPda,
+            config: configPda,
+            stats: statsPda,
+            userAccount: userAccountPda,
+            rewardMint: rewardMintPda,
+            associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Claim should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "RewardPoolEnabled");
+      }
+    });
+
+    it("Reject claims while nothing has been released", async () => {
+      try {
+        await claimFromPool();
+        assert.fail("Claim should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "RewardPoolEmpty");
+      }
+    });
+
+    it("Claim from the pool up to its balance", async () => {
+      await program.methods
+        .setEmissionRate(new anchor.BN(1_000_000))
+        .accountsStrict({
+          admin: admin.publicKey,
+          config: configPda,
+          rewardPool: rewardPoolPda,
+        })
+        .rpc();
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      const pointsBefore = (
+        await program.account.userAccount.fetch(userAccountPda)
+      ).points.toNumber();
+
+      await claimFromPool();
+
+      const paid = Number(
+        (await connection.getTokenAccountBalance(userRewardsAta)).value.amount
+      );
+      assert.equal(paid, Math.min(pointsBefore, fundAmount));
+
+      const userAccount = await program.account.userAccount.fetch(
+        userAccountPda
+      );
+      assert.equal(userAccount.points.toNumber(), pointsBefore - paid);
+
+      const rewardPool = await program.account.rewardPool.fetch(rewardPoolPda);
+      assert.equal(rewardPool.emitted.toNumber(), paid);
+      // The unclaimed backlog is capped at a week of emission
+      const maxBacklog = new anchor.BN(1_000_000).muln(7 * 24 * 60 * 60);
+      assert.ok(rewardPool.released.lte(rewardPool.emitted.add(maxBacklog)));
+    });
+  });
 });
@@ -1,4 +1,21 @@
-use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::{AccountMeta, Instruction}, program::invoke_signed},
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token_2022::spl_token_2022::{
+        extension::{transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions},
+        state::Account as TokenAccountState,
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
 
 declare_id!("8AmEEmcKgw3QuPfQsBwLiDxebeKppkf8VeWW8bTWknZ9");
 
@@ -18,9 +35,82 @@ pub mod anchor_vault_q4_25 {
         ctx.accounts.withdraw(amount)
     }
 
-    pub fn close(ctx: Context<Close>) -> Result<()> {
+    pub fn lock(ctx: Context<Lock>, mode: LockMode, beneficiary: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.lock(mode, beneficiary)
+    }
+
+    pub fn release(ctx: Context<Release>, amount: u64) -> Result<()> {
+        ctx.accounts.release(amount)
+    }
+
+    pub fn approve_spender(ctx: Context<ApproveSpender>, spender: Pubkey, amount_per_period: u64, period: i64) -> Result<()> {
+        ctx.accounts.approve_spender(spender, amount_per_period, period, &ctx.bumps)
+    }
+
+    pub fn revoke_spender(ctx: Context<RevokeSpender>) -> Result<()> {
+        ctx.accounts.revoke_spender()
+    }
+
+    pub fn spend(ctx: Context<Spend>, amount: u64) -> Result<()> {
+        ctx.accounts.spend(amount)
+    }
+
+    pub fn init_strategy_registry(ctx: Context<InitStrategyRegistry>) -> Result<()> {
+        ctx.accounts.init_strategy_registry(&ctx.bumps)
+    }
+
+    pub fn whitelist_strategy(ctx: Context<WhitelistStrategy>, program: Pubkey) -> Result<()> {
+        ctx.accounts.whitelist_strategy(program)
+    }
+
+    pub fn invest<'info>(ctx: Context<'_, '_, 'info, 'info, Strategy<'info>>, amount: u64, data: Vec<u8>) -> Result<()> {
+        ctx.accounts.invest(amount, data, ctx.remaining_accounts, ctx.bumps.strategy_signer)
+    }
+
+    pub fn divest<'info>(ctx: Context<'_, '_, 'info, 'info, Strategy<'info>>, data: Vec<u8>) -> Result<()> {
+        ctx.accounts.divest(data, ctx.remaining_accounts, ctx.bumps.strategy_signer)
+    }
+
+    pub fn create_multisig(ctx: Context<CreateMultisig>, seed: u64, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        ctx.accounts.create_multisig(seed, members, threshold, &ctx.bumps)
+    }
+
+    pub fn propose_withdrawal(ctx: Context<ProposeWithdrawal>, recipient: Pubkey, amount: u64, expires_at: i64) -> Result<()> {
+        ctx.accounts.propose_withdrawal(recipient, amount, expires_at, &ctx.bumps)
+    }
+
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        ctx.accounts.approve()
+    }
+
+    pub fn execute(ctx: Context<Execute>) -> Result<()> {
+        ctx.accounts.execute()
+    }
+
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        ctx.accounts.close_proposal()
+    }
+
+    pub fn close<'info>(ctx: Context<'_, '_, 'info, 'info, Close<'info>>) -> Result<()> {
+        ctx.accounts.sweep_token_vaults(ctx.remaining_accounts)?;
         ctx.accounts.close()
     }
+
+    pub fn deposit_spl(ctx: Context<DepositSpl>, amount: u64) -> Result<()> {
+        ctx.accounts.deposit_spl(amount, ctx.bumps.token_vault)
+    }
+
+    pub fn withdraw_spl(ctx: Context<WithdrawSpl>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_spl(amount)
+    }
+
+    pub fn deposit_nft(ctx: Context<DepositNft>) -> Result<()> {
+        ctx.accounts.deposit_nft(ctx.bumps.token_vault)
+    }
+
+    pub fn withdraw_nft(ctx: Context<WithdrawNft>) -> Result<()> {
+        ctx.accounts.withdraw_nft()
+    }
 }
 
 #[derive(Accounts)]
@@ -78,6 +168,7 @@ pub struct Deposit<'info> {
     )]
     pub vault: SystemAccount<'info>,
     #[account(
+        mut,
         seeds = [b"state", user.key().as_ref()],
         bump = vault_state.state_bump,
     )]
@@ -98,6 +189,10 @@ impl<'info> Deposit<'info> {
 
         transfer(cpi_ctx, amount)?;
 
+        self.vault_state.deposited = self.vault_state.deposited
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
         Ok(())
     }
 }
@@ -113,6 +208,7 @@ pub struct Withdraw<'info> {
     )]
     pub vault: SystemAccount<'info>,
     #[account(
+        mut,
         seeds = [b"state", user.key().as_ref()],
         bump = vault_state.state_bump,
     )]
@@ -122,6 +218,13 @@ pub struct Withdraw<'info> {
 
 impl<'info> Withdraw<'info> {
     pub fn withdraw(&mut self, amount: u64) -> Result<()> {
+        // Vaults with a beneficiary are paid out through `release`
+        require!(self.vault_state.beneficiary.is_none(), VaultError::InvalidBeneficiary);
+
+        let available = self.vault.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(self.vault.to_account_info().data_len()));
+        self.vault_state.record_withdrawal(available, amount, Clock::get()?.unix_timestamp)?;
+
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
@@ -143,6 +246,87 @@ impl<'info> Withdraw<'info> {
     }
 }
 
+#[derive(Accounts)]
+pub struct Lock<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+impl<'info> Lock<'info> {
+    pub fn lock(&mut self, mode: LockMode, beneficiary: Option<Pubkey>) -> Result<()> {
+        // Once set, a lock can't be changed or lifted by the owner
+        require!(self.vault_state.lock == LockMode::Unlocked, VaultError::VaultLocked);
+        require!(beneficiary != Some(self.user.key()), VaultError::InvalidBeneficiary);
+
+        match mode {
+            LockMode::Unlocked => return err!(VaultError::InvalidSchedule),
+            LockMode::TimeLock { .. } => {}
+            LockMode::Vesting { start_ts, cliff_ts, end_ts } => {
+                require!(start_ts <= cliff_ts && cliff_ts <= end_ts && start_ts < end_ts, VaultError::InvalidSchedule);
+            }
+        }
+
+        self.vault_state.lock = mode;
+        self.vault_state.beneficiary = beneficiary;
+        self.vault_state.released = 0;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Release<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub owner: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", owner.key().as_ref()],
+        bump = vault_state.state_bump,
+        constraint = vault_state.beneficiary == Some(beneficiary.key()) @ VaultError::InvalidBeneficiary,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Release<'info> {
+    pub fn release(&mut self, amount: u64) -> Result<()> {
+        let available = self.vault.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(self.vault.to_account_info().data_len()));
+        self.vault_state.record_withdrawal(available, amount, Clock::get()?.unix_timestamp)?;
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.vault.to_account_info(),
+            to: self.beneficiary.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            cpi_program,
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+}
+
 #[derive(Accounts)]
 pub struct Close<'info> {
     #[account(mut)]
@@ -164,7 +348,83 @@ pub struct Close<'info> {
 }
 
 impl<'info> Close<'info> {
+    /// Empties and closes the token vaults passed as `[mint, vault_ata, user_ata,
+    /// token_vault, token_program]` groups in the remaining accounts, so SPL Token and
+    /// Token-2022 mints can be swept together. Every open token vault must be passed, and
+    /// mints with a transfer fee must be writable.
+    pub fn sweep_token_vaults(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(remaining_accounts.len() % 5 == 0, VaultError::InvalidTokenVault);
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        for accounts in remaining_accounts.chunks(5) {
+            let mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+            let vault_ata = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+            let user_ata = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+            let token_vault = Account::<TokenVault>::try_from(&accounts[3])?;
+            let token_program = Interface::<TokenInterface>::try_from(&accounts[4])?;
+
+            let (token_vault_address, _) = Pubkey::find_program_address(
+                &[b"token_vault", self.vault_state.key().as_ref(), mint.key().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(token_vault.key(), token_vault_address, VaultError::InvalidTokenVault);
+
+            require_keys_eq!(*accounts[0].owner, token_program.key(), VaultError::InvalidTokenVault);
+            require_keys_eq!(
+                vault_ata.key(),
+                get_associated_token_address_with_program_id(&self.vault.key(), &mint.key(), &token_program.key()),
+                VaultError::InvalidTokenVault
+            );
+            require_keys_eq!(user_ata.mint, mint.key(), VaultError::InvalidTokenVault);
+            require_keys_eq!(user_ata.owner, self.user.key(), VaultError::InvalidTokenVault);
+
+            if vault_ata.amount > 0 {
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: vault_ata.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: user_ata.to_account_info(),
+                        authority: self.vault.to_account_info(),
+                    },
+                    signer_seeds,
+                );
+
+                transfer_checked(cpi_ctx, vault_ata.amount, mint.decimals)?;
+            }
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: vault_ata.to_account_info(),
+                    destination: self.user.to_account_info(),
+                    authority: self.vault.to_account_info(),
+                },
+                signer_seeds,
+            );
+
+            close_vault_ata(cpi_ctx, mint.to_account_info())?;
+
+            token_vault.close(self.user.to_account_info())?;
+            self.vault_state.close_token_vault()?;
+        }
+
+        require!(self.vault_state.token_vaults == 0, VaultError::TokenVaultsOpen);
+
+        Ok(())
+    }
+
     pub fn close(&mut self) -> Result<()> {
+        require!(self.vault_state.invested == 0, VaultError::StrategyFundsOpen);
+        require!(self.vault_state.is_unlocked(Clock::get()?.unix_timestamp), VaultError::VaultLocked);
+
+        // Whatever is left belongs to the beneficiary, so it must be released first
+        if self.vault_state.beneficiary.is_some() {
+            let rent_exempt = Rent::get()?.minimum_balance(self.vault.to_account_info().data_len());
+            require!(self.vault.lamports() <= rent_exempt, VaultError::VaultLocked);
+        }
+
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
@@ -180,6 +440,7 @@ impl<'info> Close<'info> {
             signer_seeds,
         );
 
+        // The balance also holds the rent-exempt reserve and any lamports sent to the vault directly
         let balance = self.vault.to_account_info().lamports();
 
         transfer(cpi_ctx, balance)?;
@@ -188,9 +449,1187 @@ impl<'info> Close<'info> {
     }
 }
 
-#[derive(InitSpace)]
-#[account]
-pub struct VaultState {
-    pub vault_bump: u8,
-    pub state_bump: u8,
+/// Closes a token account owned by the vault. Token-2022 refuses to close an account that
+/// holds withheld transfer fees, so those are harvested to the mint first, which must then
+/// be writable.
+fn close_vault_ata<'info>(
+    cpi_ctx: CpiContext<'_, '_, '_, 'info, CloseAccount<'info>>,
+    mint: AccountInfo<'info>,
+) -> Result<()> {
+    let withheld = {
+        let data = cpi_ctx.accounts.account.try_borrow_data()?;
+        StateWithExtensions::<TokenAccountState>::unpack(&data)?
+            .get_extension::<TransferFeeAmount>()
+            .map_or(0, |fee_amount| u64::from(fee_amount.withheld_amount))
+    };
+
+    if withheld > 0 {
+        let harvest_ctx = CpiContext::new(
+            cpi_ctx.program.clone(),
+            HarvestWithheldTokensToMint {
+                token_program_id: cpi_ctx.program.clone(),
+                mint,
+            },
+        );
+
+        harvest_withheld_tokens_to_mint(harvest_ctx, vec![cpi_ctx.accounts.account.clone()])?;
+    }
+
+    close_account(cpi_ctx)
+}
+
+#[derive(Accounts)]
+pub struct DepositSpl<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"token_vault", vault_state.key().as_ref(), mint.key().as_ref()],
+        bump,
+        space = TokenVault::DISCRIMINATOR.len() + TokenVault::INIT_SPACE,
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositSpl<'info> {
+    pub fn deposit_spl(&mut self, amount: u64, bump: u8) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        self.vault_state.open_token_vault(&mut self.token_vault, self.mint.key(), bump)?;
+
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.user_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.vault_ata.to_account_info(),
+                authority: self.user.to_account_info(),
+            },
+        );
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSpl<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"token_vault", vault_state.key().as_ref(), mint.key().as_ref()],
+        bump = token_vault.bump,
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawSpl<'info> {
+    pub fn withdraw_spl(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.vault_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.user_ata.to_account_info(),
+                authority: self.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        self.vault_ata.reload()?;
+        if self.vault_ata.amount > 0 {
+            return Ok(());
+        }
+
+        // Give the rent of an emptied token vault back to the user
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault_ata.to_account_info(),
+                destination: self.user.to_account_info(),
+                authority: self.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        close_vault_ata(cpi_ctx, self.mint.to_account_info())?;
+
+        self.token_vault.close(self.user.to_account_info())?;
+        self.vault_state.close_token_vault()
+    }
+}
+
+#[derive(Accounts)]
+pub struct DepositNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mint::decimals = 0,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Account<'info, MasterEditionAccount>,
+    #[account(
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"token_vault", vault_state.key().as_ref(), mint.key().as_ref()],
+        bump,
+        space = TokenVault::DISCRIMINATOR.len() + TokenVault::INIT_SPACE,
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositNft<'info> {
+    pub fn deposit_nft(&mut self, bump: u8) -> Result<()> {
+        self.vault_state.open_token_vault(&mut self.token_vault, self.mint.key(), bump)?;
+
+        // A master edition guarantees a supply of one
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.user_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.vault_ata.to_account_info(),
+                authority: self.user.to_account_info(),
+            },
+        );
+
+        transfer_checked(cpi_ctx, 1, 0)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        mint::decimals = 0,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"token_vault", vault_state.key().as_ref(), mint.key().as_ref()],
+        bump = token_vault.bump,
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawNft<'info> {
+    pub fn withdraw_nft(&mut self) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.vault_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.user_ata.to_account_info(),
+                authority: self.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, self.vault_ata.amount, 0)?;
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault_ata.to_account_info(),
+                destination: self.user.to_account_info(),
+                authority: self.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        close_vault_ata(cpi_ctx, self.mint.to_account_info())?;
+
+        self.token_vault.close(self.user.to_account_info())?;
+        self.vault_state.close_token_vault()
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(spender: Pubkey)]
+pub struct ApproveSpender<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"allowance", vault_state.key().as_ref(), spender.as_ref()],
+        bump,
+        space = Allowance::DISCRIMINATOR.len() + Allowance::INIT_SPACE,
+    )]
+    pub allowance: Account<'info, Allowance>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveSpender<'info> {
+    /// Creates or replaces the allowance of `spender`, starting a fresh period.
+    pub fn approve_spender(&mut self, spender: Pubkey, amount_per_period: u64, period: i64, bumps: &ApproveSpenderBumps) -> Result<()> {
+        require!(period > 0, VaultError::InvalidPeriod);
+
+        self.allowance.set_inner(Allowance {
+            vault_state: self.vault_state.key(),
+            spender,
+            amount_per_period,
+            period,
+            period_start: Clock::get()?.unix_timestamp,
+            spent: 0,
+            bump: bumps.allowance,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeSpender<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        close = user,
+        has_one = vault_state,
+        seeds = [b"allowance", vault_state.key().as_ref(), allowance.spender.as_ref()],
+        bump = allowance.bump,
+    )]
+    pub allowance: Account<'info, Allowance>,
+}
+
+impl<'info> RevokeSpender<'info> {
+    pub fn revoke_spender(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Spend<'info> {
+    pub spender: Signer<'info>,
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+    pub owner: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", owner.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        has_one = vault_state,
+        has_one = spender,
+        seeds = [b"allowance", vault_state.key().as_ref(), spender.key().as_ref()],
+        bump = allowance.bump,
+    )]
+    pub allowance: Account<'info, Allowance>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Spend<'info> {
+    pub fn spend(&mut self, amount: u64) -> Result<()> {
+        // Funds set aside for a beneficiary can't be spent by the owner's delegates either
+        require!(self.vault_state.beneficiary.is_none(), VaultError::InvalidBeneficiary);
+
+        self.allowance.record_spend(amount, Clock::get()?.unix_timestamp)?;
+
+        let available = self.vault.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(self.vault.to_account_info().data_len()));
+        self.vault_state.record_withdrawal(available, amount, Clock::get()?.unix_timestamp)?;
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.vault.to_account_info(),
+            to: self.recipient.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            cpi_program,
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitStrategyRegistry<'info> {
+    /// Only the program's upgrade authority can become the registry admin.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ VaultError::Unauthorized)]
+    pub program: Program<'info, crate::program::AnchorVaultQ425>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ VaultError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"strategies"],
+        bump,
+        space = StrategyRegistry::DISCRIMINATOR.len() + StrategyRegistry::INIT_SPACE,
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitStrategyRegistry<'info> {
+    pub fn init_strategy_registry(&mut self, bumps: &InitStrategyRegistryBumps) -> Result<()> {
+        self.registry.set_inner(StrategyRegistry {
+            admin: self.admin.key(),
+            programs: Vec::new(),
+            bump: bumps.registry,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WhitelistStrategy<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"strategies"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+}
+
+impl<'info> WhitelistStrategy<'info> {
+    pub fn whitelist_strategy(&mut self, program: Pubkey) -> Result<()> {
+        if self.registry.programs.contains(&program) {
+            return Ok(());
+        }
+
+        require!(self.registry.programs.len() < MAX_STRATEGIES, VaultError::RegistryFull);
+        self.registry.programs.push(program);
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Strategy<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault_state.key().as_ref()],
+        bump = vault_state.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", user.key().as_ref()],
+        bump = vault_state.state_bump,
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        seeds = [b"strategies"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+    /// Signs strategy calls in place of the vault, which also owns the token vaults.
+    /// It only holds lamports for the duration of an `invest` or `divest`.
+    #[account(
+        mut,
+        seeds = [b"strategy", vault_state.key().as_ref()],
+        bump,
+    )]
+    pub strategy_signer: SystemAccount<'info>,
+    /// CHECK: Must be a whitelisted executable program, checked in `check_strategy`
+    #[account(executable)]
+    pub strategy_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Strategy<'info> {
+    /// Moves up to `amount` lamports into the strategy. The strategy instruction in
+    /// `data` runs with the strategy signer holding `amount`, over the remaining
+    /// accounts. Whatever the strategy doesn't take goes back to the vault.
+    pub fn invest(&mut self, amount: u64, data: Vec<u8>, remaining_accounts: &'info [AccountInfo<'info>], bump: u8) -> Result<()> {
+        // Vaults with a beneficiary are paid out through `release`
+        require!(self.vault_state.beneficiary.is_none(), VaultError::InvalidBeneficiary);
+        self.check_strategy()?;
+
+        // Only rent-exempt, unlocked funds can be put to work
+        let available = self.vault.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(self.vault.to_account_info().data_len()));
+        require!(amount <= available, VaultError::InsufficientFunds);
+        require!(amount <= self.vault_state.releasable(available, Clock::get()?.unix_timestamp)?, VaultError::VaultLocked);
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.vault_state.to_account_info().key.as_ref(), &[self.vault_state.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.strategy_signer.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        let before = self.strategy_signer.lamports();
+        self.invoke_strategy(data, remaining_accounts, bump)?;
+        let moved = before.saturating_sub(self.strategy_signer.lamports());
+
+        require!(moved <= amount, VaultError::InvalidStrategyCpi);
+
+        self.return_to_vault(bump)?;
+
+        self.vault_state.strategy_program = Some(self.strategy_program.key());
+        self.vault_state.invested = self.vault_state.invested
+            .checked_add(moved)
+            .ok_or(VaultError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Pulls lamports back from the strategy into the vault. Returns up to the
+    /// invested amount count as principal, anything on top as yield.
+    pub fn divest(&mut self, data: Vec<u8>, remaining_accounts: &'info [AccountInfo<'info>], bump: u8) -> Result<()> {
+        self.check_strategy()?;
+        require!(
+            self.vault_state.strategy_program == Some(self.strategy_program.key()),
+            VaultError::StrategyMismatch
+        );
+
+        let before = self.strategy_signer.lamports();
+        self.invoke_strategy(data, remaining_accounts, bump)?;
+        let returned = self.strategy_signer.lamports()
+            .checked_sub(before)
+            .ok_or(VaultError::InvalidStrategyCpi)?;
+
+        self.return_to_vault(bump)?;
+
+        let principal = returned.min(self.vault_state.invested);
+        self.vault_state.invested -= principal;
+        self.vault_state.yield_earned = self.vault_state.yield_earned
+            .checked_add(returned - principal)
+            .ok_or(VaultError::Overflow)?;
+
+        if self.vault_state.invested == 0 {
+            self.vault_state.strategy_program = None;
+        }
+
+        Ok(())
+    }
+
+    fn check_strategy(&self) -> Result<()> {
+        require!(
+            self.registry.programs.contains(&self.strategy_program.key()),
+            VaultError::StrategyNotWhitelisted
+        );
+
+        // All funds must come back from one strategy before moving to another
+        if let Some(strategy_program) = self.vault_state.strategy_program {
+            require_keys_eq!(strategy_program, self.strategy_program.key(), VaultError::StrategyMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn invoke_strategy(&self, data: Vec<u8>, remaining_accounts: &'info [AccountInfo<'info>], bump: u8) -> Result<()> {
+        let strategy_signer = self.strategy_signer.key();
+
+        let accounts = remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || *account.key == strategy_signer,
+                is_writable: account.is_writable,
+            })
+            .collect();
+
+        let ix = Instruction {
+            program_id: self.strategy_program.key(),
+            accounts,
+            data,
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"strategy", self.vault_state.to_account_info().key.as_ref(), &[bump]]];
+
+        let mut account_infos = remaining_accounts.to_vec();
+        account_infos.push(self.strategy_program.to_account_info());
+
+        invoke_signed(&ix, &account_infos, signer_seeds)?;
+
+        // The strategy signer must stay a plain system account so it can be emptied
+        require_keys_eq!(*self.strategy_signer.to_account_info().owner, System::id(), VaultError::InvalidStrategyCpi);
+
+        Ok(())
+    }
+
+    /// Moves everything left on the strategy signer back into the vault.
+    fn return_to_vault(&self, bump: u8) -> Result<()> {
+        let balance = self.strategy_signer.lamports();
+        if balance == 0 {
+            return Ok(());
+        }
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"strategy", self.vault_state.to_account_info().key.as_ref(), &[bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.strategy_signer.to_account_info(),
+                to: self.vault.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, balance)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateMultisig<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"multisig", creator.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = Multisig::DISCRIMINATOR.len() + Multisig::INIT_SPACE,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateMultisig<'info> {
+    pub fn create_multisig(&mut self, seed: u64, members: Vec<Pubkey>, threshold: u8, bumps: &CreateMultisigBumps) -> Result<()> {
+        require!(members.len() <= MAX_MEMBERS, VaultError::TooManyMembers);
+        require!(threshold > 0 && threshold as usize <= members.len(), VaultError::InvalidThreshold);
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), VaultError::DuplicateMember);
+        }
+
+        // Fund the vault with its rent-exempt minimum, like a single-owner vault
+        let rent_exempt = Rent::get()?.minimum_balance(self.vault.to_account_info().data_len());
+
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.creator.to_account_info(),
+                to: self.vault.to_account_info(),
+            },
+        );
+
+        transfer(cpi_ctx, rent_exempt)?;
+
+        self.multisig.set_inner(Multisig {
+            creator: self.creator.key(),
+            seed,
+            members,
+            threshold,
+            proposals: 0,
+            vault_bump: bumps.vault,
+            bump: bumps.multisig,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ProposeWithdrawal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"proposal", multisig.key().as_ref(), multisig.proposals.to_le_bytes().as_ref()],
+        bump,
+        space = Proposal::DISCRIMINATOR.len() + Proposal::INIT_SPACE,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProposeWithdrawal<'info> {
+    pub fn propose_withdrawal(&mut self, recipient: Pubkey, amount: u64, expires_at: i64, bumps: &ProposeWithdrawalBumps) -> Result<()> {
+        let member = self.multisig.member_index(&self.proposer.key())?;
+
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(expires_at > Clock::get()?.unix_timestamp, VaultError::ProposalExpired);
+
+        // The proposer approves their own proposal
+        self.proposal.set_inner(Proposal {
+            multisig: self.multisig.key(),
+            index: self.multisig.proposals,
+            proposer: self.proposer.key(),
+            recipient,
+            amount,
+            approvals: 1 << member,
+            expires_at,
+            bump: bumps.proposal,
+        });
+
+        self.multisig.proposals += 1;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    pub member: Signer<'info>,
+    #[account(
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        has_one = multisig,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+impl<'info> Approve<'info> {
+    pub fn approve(&mut self) -> Result<()> {
+        let member = self.multisig.member_index(&self.member.key())?;
+
+        require!(Clock::get()?.unix_timestamp < self.proposal.expires_at, VaultError::ProposalExpired);
+        require!(self.proposal.approvals & (1 << member) == 0, VaultError::AlreadyApproved);
+
+        self.proposal.approvals |= 1 << member;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    pub member: Signer<'info>,
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+    #[account(
+        seeds = [b"multisig", multisig.creator.as_ref(), multisig.seed.to_le_bytes().as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump = multisig.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = multisig,
+        has_one = proposer,
+        has_one = recipient,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Execute<'info> {
+    pub fn execute(&mut self) -> Result<()> {
+        self.multisig.member_index(&self.member.key())?;
+
+        require!(Clock::get()?.unix_timestamp < self.proposal.expires_at, VaultError::ProposalExpired);
+        require!(self.proposal.approvals.count_ones() >= self.multisig.threshold as u32, VaultError::NotEnoughApprovals);
+
+        // The vault must stay rent exempt
+        let available = self.vault.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(self.vault.to_account_info().data_len()));
+        require!(self.proposal.amount <= available, VaultError::InsufficientFunds);
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.vault.to_account_info(),
+            to: self.recipient.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", self.multisig.to_account_info().key.as_ref(), &[self.multisig.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            cpi_program,
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, self.proposal.amount)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"proposal", proposal.multisig.as_ref(), proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+impl<'info> CloseProposal<'info> {
+    /// Returns the rent of a proposal that expired without being executed.
+    pub fn close_proposal(&mut self) -> Result<()> {
+        require!(Clock::get()?.unix_timestamp >= self.proposal.expires_at, VaultError::ProposalNotExpired);
+
+        Ok(())
+    }
+}
+
+/// One per mint a vault holds, created by the owner's first deposit of it. Open token
+/// vaults are counted from these rather than from token accounts anyone can create.
+#[derive(InitSpace)]
+#[account]
+pub struct TokenVault {
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(InitSpace)]
+#[account]
+pub struct VaultState {
+    pub vault_bump: u8,
+    pub state_bump: u8,
+    /// Number of open `TokenVault` records, which must all be swept before closing.
+    pub token_vaults: u16,
+    /// Restricts when SOL can leave the vault. Token vaults are not affected.
+    pub lock: LockMode,
+    /// Receives the SOL released under the lock instead of the owner.
+    pub beneficiary: Option<Pubkey>,
+    /// Lamports paid out since the lock was set.
+    pub released: u64,
+    /// Lifetime lamports deposited, excluding the rent-exempt reserve.
+    pub deposited: u64,
+    /// Lifetime lamports withdrawn or released.
+    pub withdrawn: u64,
+    /// Strategy program currently holding `invested`, if any.
+    pub strategy_program: Option<Pubkey>,
+    /// Principal lamports currently deployed to the strategy.
+    pub invested: u64,
+    /// Lifetime lamports returned by strategies on top of the principal.
+    pub yield_earned: u64,
+}
+
+impl VaultState {
+    /// Records `token_vault` the first time a mint is deposited.
+    pub fn open_token_vault(&mut self, token_vault: &mut TokenVault, mint: Pubkey, bump: u8) -> Result<()> {
+        if token_vault.mint == mint {
+            return Ok(());
+        }
+
+        token_vault.mint = mint;
+        token_vault.bump = bump;
+        self.token_vaults = self.token_vaults
+            .checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn close_token_vault(&mut self) -> Result<()> {
+        self.token_vaults = self.token_vaults
+            .checked_sub(1)
+            .ok_or(VaultError::InvalidTokenVault)?;
+
+        Ok(())
+    }
+
+    /// Checks that `amount` can leave the vault given the `available` balance above
+    /// rent exemption and the lock, then records it.
+    pub fn record_withdrawal(&mut self, available: u64, amount: u64, now: i64) -> Result<()> {
+        // The vault must stay rent exempt until it is closed
+        require!(amount <= available, VaultError::InsufficientFunds);
+
+        let releasable = self.releasable(available, now)?;
+        require!(amount <= releasable, VaultError::VaultLocked);
+
+        self.released = self.released
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        self.withdrawn = self.withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        match self.lock {
+            LockMode::Unlocked => true,
+            LockMode::TimeLock { unlock_ts } => now >= unlock_ts,
+            LockMode::Vesting { end_ts, .. } => now >= end_ts,
+        }
+    }
+
+    /// Lamports that can leave the vault at `now`, given the `available` balance
+    /// above rent exemption. Vesting applies to everything ever held under the lock,
+    /// so later deposits vest on the same schedule.
+    pub fn releasable(&self, available: u64, now: i64) -> Result<u64> {
+        let LockMode::Vesting { start_ts, cliff_ts, end_ts } = self.lock else {
+            return Ok(if self.is_unlocked(now) { available } else { 0 });
+        };
+
+        if now < cliff_ts {
+            return Ok(0);
+        }
+        if now >= end_ts {
+            return Ok(available);
+        }
+
+        let total = available as u128 + self.released as u128;
+        let vested = total
+            .checked_mul((now - start_ts) as u128)
+            .and_then(|v| v.checked_div((end_ts - start_ts) as u128))
+            .ok_or(VaultError::InvalidSchedule)?;
+
+        Ok((vested as u64).saturating_sub(self.released).min(available))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum LockMode {
+    Unlocked,
+    /// Nothing can be withdrawn before `unlock_ts`.
+    TimeLock { unlock_ts: i64 },
+    /// Releases linearly from `start_ts` to `end_ts`, with nothing available before `cliff_ts`.
+    Vesting { start_ts: i64, cliff_ts: i64, end_ts: i64 },
+}
+
+#[derive(InitSpace)]
+#[account]
+pub struct Allowance {
+    pub vault_state: Pubkey,
+    pub spender: Pubkey,
+    pub amount_per_period: u64,
+    /// Period length in seconds.
+    pub period: i64,
+    pub period_start: i64,
+    /// Lamports spent in the current period.
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl Allowance {
+    /// Rolls over to the current period if the last one ended, then records `amount`
+    /// against what is left of it.
+    pub fn record_spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        // A period ending past the end of time never rolls over
+        if self.period_start.checked_add(self.period).is_some_and(|end| now >= end) {
+            // Keep periods aligned to the approval time even if some were skipped
+            self.period_start = now - (now - self.period_start) % self.period;
+            self.spent = 0;
+        }
+
+        let spent = self.spent
+            .checked_add(amount)
+            .filter(|spent| *spent <= self.amount_per_period)
+            .ok_or(VaultError::AllowanceExceeded)?;
+        self.spent = spent;
+
+        Ok(())
+    }
+}
+
+pub const MAX_STRATEGIES: usize = 8;
+
+#[derive(InitSpace)]
+#[account]
+pub struct StrategyRegistry {
+    pub admin: Pubkey,
+    /// Programs vault owners may move funds into.
+    #[max_len(MAX_STRATEGIES)]
+    pub programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Members are tracked by position in a `u16` approval bitmap.
+pub const MAX_MEMBERS: usize = 16;
+
+#[derive(InitSpace)]
+#[account]
+pub struct Multisig {
+    pub creator: Pubkey,
+    pub seed: u64,
+    #[max_len(MAX_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Number of proposals created so far, used as the next proposal index.
+    pub proposals: u64,
+    pub vault_bump: u8,
+    pub bump: u8,
+}
+
+impl Multisig {
+    pub fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|member| member == key)
+            .ok_or(VaultError::NotMember.into())
+    }
+}
+
+#[derive(InitSpace)]
+#[account]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    /// Bit `i` is set once `members[i]` approved.
+    pub approvals: u16,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+#[error_code]
+pub enum VaultError {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Token vault accounts do not match the vault")]
+    InvalidTokenVault,
+    #[msg("Token vaults must be swept before closing")]
+    TokenVaultsOpen,
+    #[msg("Amount exceeds what the lock releases")]
+    VaultLocked,
+    #[msg("Invalid lock schedule")]
+    InvalidSchedule,
+    #[msg("Invalid beneficiary")]
+    InvalidBeneficiary,
+    #[msg("Signer is not a multisig member")]
+    NotMember,
+    #[msg("Too many multisig members")]
+    TooManyMembers,
+    #[msg("Duplicate multisig member")]
+    DuplicateMember,
+    #[msg("Threshold must be between one and the number of members")]
+    InvalidThreshold,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Member already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Proposal does not have enough approvals")]
+    NotEnoughApprovals,
+    #[msg("Withdrawal would leave the vault below rent exemption")]
+    InsufficientFunds,
+    #[msg("Allowance period must be positive")]
+    InvalidPeriod,
+    #[msg("Amount exceeds the remaining allowance for this period")]
+    AllowanceExceeded,
+    #[msg("Strategy registry is full")]
+    RegistryFull,
+    #[msg("Strategy program is not whitelisted")]
+    StrategyNotWhitelisted,
+    #[msg("Funds are deployed to a different strategy")]
+    StrategyMismatch,
+    #[msg("Strategy moved funds it was not allowed to")]
+    InvalidStrategyCpi,
+    #[msg("Funds must be pulled out of the strategy before closing")]
+    StrategyFundsOpen,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Proposal has not expired yet")]
+    ProposalNotExpired,
+    #[msg("Signer is not the program upgrade authority")]
+    Unauthorized,
 }
@@ -0,0 +1,77 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+declare_id!("EW87c4X4eDDBY9QnEmTvFar9LMjUtEJYWUJdv8wb43sG");
+
+/// Stand-in for a yield strategy: deposits sit in a pool PDA per depositor, and
+/// anything sent to the pool on top of them is paid out as yield on withdrawal.
+#[program]
+pub mod mock_strategy {
+    use super::*;
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        ctx.accounts.deposit(amount)
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+        ctx.accounts.withdraw(&ctx.bumps)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", depositor.key().as_ref()],
+        bump,
+    )]
+    pub pool: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Deposit<'info> {
+    pub fn deposit(&mut self, amount: u64) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.depositor.to_account_info(),
+                to: self.pool.to_account_info(),
+            },
+        );
+
+        transfer(cpi_ctx, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub depositor: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", depositor.key().as_ref()],
+        bump,
+    )]
+    pub pool: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Withdraw<'info> {
+    /// Pays the whole pool, principal and yield, back to the depositor.
+    pub fn withdraw(&mut self, bumps: &WithdrawBumps) -> Result<()> {
+        let depositor = self.depositor.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"pool", depositor.as_ref(), &[bumps.pool]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.pool.to_account_info(),
+                to: self.depositor.to_account_info(),
+            },
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, self.pool.lamports())
+    }
+}
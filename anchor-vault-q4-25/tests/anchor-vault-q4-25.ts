@@ -2,6 +2,15 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { AnchorVaultQ425 } from "../target/types/anchor_vault_q4_25";
 import { expect } from "chai";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
 
 describe("anchor_vault_q4_25", () => {
   const provider = anchor.AnchorProvider.env();
@@ -21,11 +30,26 @@ describe("anchor_vault_q4_25", () => {
     program.programId
   );
 
+  let mint: anchor.web3.PublicKey;
+  let userAta: anchor.web3.PublicKey;
+  let vaultAta: anchor.web3.PublicKey;
+  let tokenVault: anchor.web3.PublicKey;
+
   before(async () => {
     // Airdrop for fees 
     await provider.connection.requestAirdrop(user, 10 * anchor.web3.LAMPORTS_PER_SOL);
     // Wait for confirmation
     await new Promise(resolve => setTimeout(resolve, 1000));
+
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    mint = await createMint(provider.connection, payer, user, null, 6);
+    userAta = (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, user)).address;
+    await mintTo(provider.connection, payer, mint, userAta, payer, 1_000_000);
+    vaultAta = getAssociatedTokenAddressSync(mint, vaultPda, true);
+    [tokenVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), vaultStatePda.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
   });
 
   it("Initialize the vault", async () => {
@@ -96,9 +120,73 @@ describe("anchor_vault_q4_25", () => {
     expect(finalUserBalance).to.equal(initialUserBalance + withdrawAmount - 5000);
   });
 
+  it("Deposit SPL tokens into the vault", async () => {
+    await program.methods
+      .depositSpl(new anchor.BN(600_000))
+      .accountsStrict({
+        user: user,
+        mint: mint,
+        userAta: userAta,
+        vault: vaultPda,
+        vaultAta: vaultAta,
+        tokenVault: tokenVault,
+        vaultState: vaultStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    expect(Number((await getAccount(provider.connection, vaultAta)).amount)).to.equal(600_000);
+    expect(Number((await getAccount(provider.connection, userAta)).amount)).to.equal(400_000);
+
+    const vaultState = await program.account.vaultState.fetch(vaultStatePda);
+    expect(vaultState.tokenVaults).to.equal(1);
+  });
+
+  it("Withdraw SPL tokens from the vault", async () => {
+    await program.methods
+      .withdrawSpl(new anchor.BN(100_000))
+      .accountsStrict({
+        user: user,
+        mint: mint,
+        userAta: userAta,
+        vault: vaultPda,
+        vaultAta: vaultAta,
+        tokenVault: tokenVault,
+        vaultState: vaultStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    expect(Number((await getAccount(provider.connection, vaultAta)).amount)).to.equal(500_000);
+    expect(Number((await getAccount(provider.connection, userAta)).amount)).to.equal(500_000);
+  });
+
+  it("Refuse to close while a token vault is open", async () => {
+    try {
+      await program.methods
+        .close()
+        .accountsStrict({
+          user: user,
+          vault: vaultPda,
+          vaultState: vaultStatePda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("close should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("TokenVaultsOpen");
+    }
+  });
+
   it("Close the vault", async () => {
     const initialVaultBalance = await provider.connection.getBalance(vaultPda);
     const initialVaultStateBalance = await provider.connection.getBalance(vaultStatePda);
+    const initialVaultAtaBalance = await provider.connection.getBalance(vaultAta);
+    const initialTokenVaultBalance = await provider.connection.getBalance(tokenVault);
     const initialUserBalance = await provider.connection.getBalance(user);
 
     await program.methods
@@ -109,6 +197,13 @@ describe("anchor_vault_q4_25", () => {
         vaultState: vaultStatePda,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
+      .remainingAccounts([
+        { pubkey: mint, isSigner: false, isWritable: true },
+        { pubkey: vaultAta, isSigner: false, isWritable: true },
+        { pubkey: userAta, isSigner: false, isWritable: true },
+        { pubkey: tokenVault, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ])
       .rpc();
 
     const finalUserBalance = await provider.connection.getBalance(user);
@@ -116,11 +211,341 @@ describe("anchor_vault_q4_25", () => {
     // Vault should be 0
     expect(await provider.connection.getBalance(vaultPda)).to.equal(0);
 
+    // Token vault should be swept and closed
+    expect(await provider.connection.getAccountInfo(vaultAta)).to.be.null;
+    expect(await provider.connection.getAccountInfo(tokenVault)).to.be.null;
+    expect(Number((await getAccount(provider.connection, userAta)).amount)).to.equal(1_000_000);
+
     // VaultState should be closed (null)
     const vaultStateInfo = await provider.connection.getAccountInfo(vaultStatePda);
     expect(vaultStateInfo).to.be.null;
 
     // User gets back the remaining balance - fees
-    expect(finalUserBalance).to.equal(initialUserBalance + initialVaultBalance + initialVaultStateBalance - 5000);
+    expect(finalUserBalance).to.equal(initialUserBalance + initialVaultBalance + initialVaultStateBalance + initialVaultAtaBalance + initialTokenVaultBalance - 5000);
+  });
+});
+describe("locked vault", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.AnchorVaultQ425 as Program<AnchorVaultQ425>;
+  const owner = anchor.web3.Keypair.generate();
+  const beneficiary = anchor.web3.Keypair.generate();
+
+  const [vaultStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("state"), owner.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), vaultStatePda.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    for (const key of [owner.publicKey, beneficiary.publicKey]) {
+      const sig = await provider.connection.requestAirdrop(key, 5 * anchor.web3.LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(sig);
+    }
+
+    await program.methods
+      .initialize()
+      .accountsStrict({
+        user: owner.publicKey,
+        vaultState: vaultStatePda,
+        vault: vaultPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(2 * anchor.web3.LAMPORTS_PER_SOL))
+      .accountsStrict({
+        user: owner.publicKey,
+        vault: vaultPda,
+        vaultState: vaultStatePda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  });
+
+  it("Lock the vault on a vesting schedule for a beneficiary", async () => {
+    const now = Math.floor(Date.now() / 1000);
+
+    // Already fully vested, so the beneficiary can release everything
+    await program.methods
+      .lock(
+        { vesting: { startTs: new anchor.BN(now - 200), cliffTs: new anchor.BN(now - 100), endTs: new anchor.BN(now - 50) } },
+        beneficiary.publicKey
+      )
+      .accountsStrict({
+        user: owner.publicKey,
+        vaultState: vaultStatePda,
+      })
+      .signers([owner])
+      .rpc();
+
+    const vaultState = await program.account.vaultState.fetch(vaultStatePda);
+    expect(vaultState.beneficiary.toBase58()).to.equal(beneficiary.publicKey.toBase58());
+  });
+
+  it("Refuse to relock the vault", async () => {
+    try {
+      await program.methods
+        .lock({ timeLock: { unlockTs: new anchor.BN(0) } }, null)
+        .accountsStrict({
+          user: owner.publicKey,
+          vaultState: vaultStatePda,
+        })
+        .signers([owner])
+        .rpc();
+      expect.fail("lock should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("VaultLocked");
+    }
+  });
+
+  it("Refuse owner withdrawals from a beneficiary vault", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(anchor.web3.LAMPORTS_PER_SOL))
+        .accountsStrict({
+          user: owner.publicKey,
+          vault: vaultPda,
+          vaultState: vaultStatePda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      expect.fail("withdraw should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InvalidBeneficiary");
+    }
+  });
+
+  it("Release the vested SOL to the beneficiary", async () => {
+    const amount = 2 * anchor.web3.LAMPORTS_PER_SOL;
+    const initialBalance = await provider.connection.getBalance(beneficiary.publicKey);
+
+    await program.methods
+      .release(new anchor.BN(amount))
+      .accountsStrict({
+        beneficiary: beneficiary.publicKey,
+        owner: owner.publicKey,
+        vault: vaultPda,
+        vaultState: vaultStatePda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([beneficiary])
+      .rpc();
+
+    expect(await provider.connection.getBalance(beneficiary.publicKey)).to.equal(initialBalance + amount - 5000);
+
+    const vaultState = await program.account.vaultState.fetch(vaultStatePda);
+    expect(vaultState.released.toNumber()).to.equal(amount);
+  });
+});
+
+describe("multisig vault", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.AnchorVaultQ425 as Program<AnchorVaultQ425>;
+  const members = [0, 1, 2].map(() => anchor.web3.Keypair.generate());
+  const recipient = anchor.web3.Keypair.generate();
+  const seed = new anchor.BN(1);
+
+  const [multisigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("multisig"), members[0].publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), multisigPda.toBuffer()],
+    program.programId
+  );
+
+  const [proposalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("proposal"), multisigPda.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const amount = anchor.web3.LAMPORTS_PER_SOL;
+
+  before(async () => {
+    for (const member of members) {
+      const sig = await provider.connection.requestAirdrop(member.publicKey, 5 * anchor.web3.LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(sig);
+    }
+  });
+
+  it("Create a 2-of-3 multisig vault", async () => {
+    await program.methods
+      .createMultisig(seed, members.map((m) => m.publicKey), 2)
+      .accountsStrict({
+        creator: members[0].publicKey,
+        multisig: multisigPda,
+        vault: vaultPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([members[0]])
+      .rpc();
+
+    // Anyone can fund the treasury with a plain transfer
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({ fromPubkey: provider.wallet.publicKey, toPubkey: vaultPda, lamports: 2 * amount })
+    );
+    await provider.sendAndConfirm(tx);
+
+    const multisig = await program.account.multisig.fetch(multisigPda);
+    expect(multisig.threshold).to.equal(2);
+    expect(multisig.members.length).to.equal(3);
+  });
+
+  it("Propose a withdrawal", async () => {
+    await program.methods
+      .proposeWithdrawal(recipient.publicKey, new anchor.BN(amount), new anchor.BN(Math.floor(Date.now() / 1000) + 3600))
+      .accountsStrict({
+        proposer: members[0].publicKey,
+        multisig: multisigPda,
+        proposal: proposalPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([members[0]])
+      .rpc();
+
+    const proposal = await program.account.proposal.fetch(proposalPda);
+    expect(proposal.approvals).to.equal(1);
+  });
+
+  it("Refuse to execute below the threshold", async () => {
+    try {
+      await program.methods
+        .execute()
+        .accountsStrict({
+          member: members[0].publicKey,
+          proposer: members[0].publicKey,
+          recipient: recipient.publicKey,
+          multisig: multisigPda,
+          vault: vaultPda,
+          proposal: proposalPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([members[0]])
+        .rpc();
+      expect.fail("execute should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("NotEnoughApprovals");
+    }
+  });
+
+  it("Approve and execute the withdrawal", async () => {
+    await program.methods
+      .approve()
+      .accountsStrict({
+        member: members[2].publicKey,
+        multisig: multisigPda,
+        proposal: proposalPda,
+      })
+      .signers([members[2]])
+      .rpc();
+
+    await program.methods
+      .execute()
+      .accountsStrict({
+        member: members[1].publicKey,
+        proposer: members[0].publicKey,
+        recipient: recipient.publicKey,
+        multisig: multisigPda,
+        vault: vaultPda,
+        proposal: proposalPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([members[1]])
+      .rpc();
+
+    expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(amount);
+    expect(await provider.connection.getAccountInfo(proposalPda)).to.be.null;
+  });
+
+  const [expiringProposalPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("proposal"), multisigPda.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  it("Refuse to execute below rent exemption", async () => {
+    // Propose draining the whole treasury, rent included
+    const vaultBalance = await provider.connection.getBalance(vaultPda);
+
+    await program.methods
+      .proposeWithdrawal(recipient.publicKey, new anchor.BN(vaultBalance), new anchor.BN(Math.floor(Date.now() / 1000) + 5))
+      .accountsStrict({
+        proposer: members[0].publicKey,
+        multisig: multisigPda,
+        proposal: expiringProposalPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([members[0]])
+      .rpc();
+
+    await program.methods
+      .approve()
+      .accountsStrict({
+        member: members[1].publicKey,
+        multisig: multisigPda,
+        proposal: expiringProposalPda,
+      })
+      .signers([members[1]])
+      .rpc();
+
+    try {
+      await program.methods
+        .execute()
+        .accountsStrict({
+          member: members[1].publicKey,
+          proposer: members[0].publicKey,
+          recipient: recipient.publicKey,
+          multisig: multisigPda,
+          vault: vaultPda,
+          proposal: expiringProposalPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([members[1]])
+        .rpc();
+      expect.fail("execute should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("InsufficientFunds");
+    }
+  });
+
+  it("Close the proposal once it expires", async () => {
+    const closeProposal = () =>
+      program.methods
+        .closeProposal()
+        .accountsStrict({
+          proposer: members[0].publicKey,
+          proposal: expiringProposalPda,
+        })
+        .signers([members[0]])
+        .rpc();
+
+    try {
+      await closeProposal();
+      expect.fail("close_proposal should fail");
+    } catch (err) {
+      expect(err.error.errorCode.code).to.equal("ProposalNotExpired");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 6000));
+
+    const proposalRent = await provider.connection.getBalance(expiringProposalPda);
+    const initialProposerBalance = await provider.connection.getBalance(members[0].publicKey);
+
+    await closeProposal();
+
+    expect(await provider.connection.getAccountInfo(expiringProposalPda)).to.be.null;
+    expect(await provider.connection.getBalance(members[0].publicKey)).to.equal(initialProposerBalance + proposalRent - 5000);
   });
-});
\ No newline at end of file
+});
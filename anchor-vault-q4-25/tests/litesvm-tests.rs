@@ -0,0 +1,1111 @@
+use anchor_lang::{
+    solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address, get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    metadata::mpl_token_metadata::{
+        self,
+        accounts::{MasterEdition, Metadata},
+        types::Key,
+    },
+    token::{spl_token, spl_token::solana_program::program_pack::Pack},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{self, TransferFeeConfig},
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+    },
+};
+use anchor_vault_q4_25::{
+    accounts, instruction, Allowance, LockMode, VaultError, VaultState, ID as PROGRAM_ID,
+};
+use borsh::BorshSerialize;
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const DEPOSIT_AMOUNT: u64 = 2 * LAMPORTS_PER_SOL;
+const ALLOWANCE_AMOUNT: u64 = LAMPORTS_PER_SOL / 2;
+const ALLOWANCE_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+struct TestEnv {
+    svm: LiteSVM,
+    user: Keypair,
+    vault_state: Pubkey,
+    vault: Pubkey,
+    rent_exempt: u64,
+}
+
+fn setup() -> TestEnv {
+    let mut svm = LiteSVM::new();
+    let user = Keypair::new();
+
+    // Deployed as upgradeable so the user can act as the upgrade authority
+    let program_bytes = include_bytes!("../target/deploy/anchor_vault_q4_25.so");
+    add_upgradeable_program(&mut svm, program_bytes, &user.pubkey());
+
+    let strategy_bytes = include_bytes!("../target/deploy/mock_strategy.so");
+    svm.add_program(mock_strategy::ID, strategy_bytes).unwrap();
+
+    svm.airdrop(&user.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let (vault_state, _) =
+        Pubkey::find_program_address(&[b"state", user.pubkey().as_ref()], &PROGRAM_ID);
+    let (vault, _) = Pubkey::find_program_address(&[b"vault", vault_state.as_ref()], &PROGRAM_ID);
+    let rent_exempt = svm.get_sysvar::<Rent>().minimum_balance(0);
+
+    let mut env = TestEnv {
+        svm,
+        user,
+        vault_state,
+        vault,
+        rent_exempt,
+    };
+
+    let initialize_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Initialize {
+            user: env.user.pubkey(),
+            vault_state,
+            vault,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {}.data(),
+    };
+    send(&mut env, &[initialize_instruction]).unwrap();
+
+    env
+}
+
+fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+fn add_upgradeable_program(svm: &mut LiteSVM, program_bytes: &[u8], upgrade_authority: &Pubkey) {
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let mut program_data = Account::new_data_with_space(
+        svm.minimum_balance_for_rent_exemption(metadata_len + program_bytes.len()),
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*upgrade_authority),
+        },
+        metadata_len + program_bytes.len(),
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program_data.data[metadata_len..].copy_from_slice(program_bytes);
+    svm.set_account(program_data_address(), program_data).unwrap();
+
+    let program_state = UpgradeableLoaderState::Program {
+        programdata_address: program_data_address(),
+    };
+    let mut program = Account::new_data(
+        svm.minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+        &program_state,
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program.executable = true;
+    svm.set_account(PROGRAM_ID, program).unwrap();
+}
+
+fn send(env: &mut TestEnv, instructions: &[Instruction]) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&env.user.pubkey()),
+        &[&env.user],
+        env.svm.latest_blockhash(),
+    );
+    let result = env
+        .svm
+        .send_transaction(tx)
+        .map(|_| ())
+        .map_err(|failed| failed.err);
+    env.svm.expire_blockhash();
+    result
+}
+
+fn deposit(env: &mut TestEnv, amount: u64) -> Result<(), TransactionError> {
+    let deposit_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Deposit {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            vault_state: env.vault_state,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { amount }.data(),
+    };
+    send(env, &[deposit_instruction])
+}
+
+fn withdraw(env: &mut TestEnv, amount: u64) -> Result<(), TransactionError> {
+    let withdraw_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Withdraw {
+            user: env.user.pubkey(),
+            vault: env.vault,
+            vault_state: env.vault_state,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Withdraw { amount }.data(),
+    };
+    send(env, &[withdraw_instruction])
+}
+
+fn close(env: &mut TestEnv) -> Result<(), TransactionError> {
+    close_with_token_vaults(env, &[])
+}
+
+/// Closes the vault, sweeping the token vault of each `(mint, user_ata)` pair.
+fn close_with_token_vaults(
+    env: &mut TestEnv,
+    token_vaults: &[(Pubkey, Pubkey)],
+) -> Result<(), TransactionError> {
+    let mut account_metas = accounts::Close {
+        user: env.user.pubkey(),
+        vault: env.vault,
+        vault_state: env.vault_state,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    for (mint, user_ata) in token_vaults {
+        let token_program = token_program_of(env, mint);
+        account_metas.extend([
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(vault_ata_address(env, mint), false),
+            AccountMeta::new(*user_ata, false),
+            AccountMeta::new(token_vault_address(env, mint), false),
+            AccountMeta::new_readonly(token_program, false),
+        ]);
+    }
+
+    let close_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: account_metas,
+        data: instruction::Close {}.data(),
+    };
+    send(env, &[close_instruction])
+}
+
+fn allowance_address(env: &TestEnv, spender: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"allowance", env.vault_state.as_ref(), spender.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+fn approve_spender(env: &mut TestEnv, spender: &Pubkey) {
+    let approve_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::ApproveSpender {
+            user: env.user.pubkey(),
+            vault_state: env.vault_state,
+            allowance: allowance_address(env, spender),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ApproveSpender {
+            spender: *spender,
+            amount_per_period: ALLOWANCE_AMOUNT,
+            period: ALLOWANCE_PERIOD,
+        }
+        .data(),
+    };
+    send(env, &[approve_instruction]).unwrap();
+}
+
+fn spend(env: &mut TestEnv, spender: &Keypair, amount: u64) -> Result<(), TransactionError> {
+    let spend_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Spend {
+            spender: spender.pubkey(),
+            recipient: spender.pubkey(),
+            owner: env.user.pubkey(),
+            vault: env.vault,
+            vault_state: env.vault_state,
+            allowance: allowance_address(env, &spender.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Spend { amount }.data(),
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[spend_instruction],
+        Some(&spender.pubkey()),
+        &[spender],
+        env.svm.latest_blockhash(),
+    );
+    let result = env
+        .svm
+        .send_transaction(tx)
+        .map(|_| ())
+        .map_err(|failed| failed.err);
+    env.svm.expire_blockhash();
+    result
+}
+
+fn warp(env: &mut TestEnv, seconds: i64) {
+    let mut clock = env.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += seconds;
+    env.svm.set_sysvar(&clock);
+}
+
+fn registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"strategies"], &PROGRAM_ID).0
+}
+
+fn strategy_signer_address(env: &TestEnv) -> Pubkey {
+    Pubkey::find_program_address(&[b"strategy", env.vault_state.as_ref()], &PROGRAM_ID).0
+}
+
+fn pool_address(env: &TestEnv) -> Pubkey {
+    let depositor = strategy_signer_address(env);
+    Pubkey::find_program_address(&[b"pool", depositor.as_ref()], &mock_strategy::ID).0
+}
+
+fn init_strategy_registry_instruction(admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::InitStrategyRegistry {
+            admin: *admin,
+            program: PROGRAM_ID,
+            program_data: program_data_address(),
+            registry: registry_address(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitStrategyRegistry {}.data(),
+    }
+}
+
+fn whitelist_mock_strategy(env: &mut TestEnv) {
+    let init_instruction = init_strategy_registry_instruction(&env.user.pubkey());
+    let whitelist_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::WhitelistStrategy {
+            admin: env.user.pubkey(),
+            registry: registry_address(),
+        }
+        .to_account_metas(None),
+        data: instruction::WhitelistStrategy {
+            program: mock_strategy::ID,
+        }
+        .data(),
+    };
+    send(env, &[init_instruction, whitelist_instruction]).unwrap();
+}
+
+/// Builds an `invest` or `divest` instruction forwarding `strategy_data` to the
+/// mock strategy over the strategy signer and its pool.
+fn strategy_instruction(env: &TestEnv, data: Vec<u8>) -> Instruction {
+    let mut accounts = accounts::Strategy {
+        user: env.user.pubkey(),
+        vault: env.vault,
+        vault_state: env.vault_state,
+        registry: registry_address(),
+        strategy_signer: strategy_signer_address(env),
+        strategy_program: mock_strategy::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend([
+        AccountMeta::new(strategy_signer_address(env), false),
+        AccountMeta::new(pool_address(env), false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+fn invest(env: &mut TestEnv, amount: u64, strategy_amount: u64) -> Result<(), TransactionError> {
+    let data = instruction::Invest {
+        amount,
+        data: mock_strategy::instruction::Deposit {
+            amount: strategy_amount,
+        }
+        .data(),
+    }
+    .data();
+    let invest_instruction = strategy_instruction(env, data);
+    send(env, &[invest_instruction])
+}
+
+fn divest(env: &mut TestEnv) -> Result<(), TransactionError> {
+    let data = instruction::Divest {
+        data: mock_strategy::instruction::Withdraw {}.data(),
+    }
+    .data();
+    let divest_instruction = strategy_instruction(env, data);
+    send(env, &[divest_instruction])
+}
+
+/// Creates a mint with the user as authority and a user ATA holding `amount`.
+fn create_funded_mint(env: &mut TestEnv, amount: u64) -> (Pubkey, Pubkey) {
+    let mint = Keypair::new();
+    let user = env.user.pubkey();
+    let space = spl_token::state::Mint::LEN;
+
+    let instructions = [
+        solana_sdk::system_instruction::create_account(
+            &user,
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &user, None, 6)
+            .unwrap(),
+        create_associated_token_account(&user, &user, &mint.pubkey(), &spl_token::ID),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user),
+        &[&env.user, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+
+    let user_ata = get_associated_token_address(&user, &mint.pubkey());
+    mint_to(env, &mint.pubkey(), &user_ata, amount);
+
+    (mint.pubkey(), user_ata)
+}
+
+/// Creates a Token-2022 mint charging `fee_bps` on every transfer, with the user as
+/// authority and a user ATA holding `amount`.
+fn create_funded_fee_mint(env: &mut TestEnv, amount: u64, fee_bps: u16) -> (Pubkey, Pubkey) {
+    let mint = Keypair::new();
+    let user = env.user.pubkey();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+
+    let instructions = [
+        solana_sdk::system_instruction::create_account(
+            &user,
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &spl_token_2022::ID,
+        ),
+        transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            Some(&user),
+            Some(&user),
+            fee_bps,
+            u64::MAX,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &user,
+            None,
+            6,
+        )
+        .unwrap(),
+        create_associated_token_account(&user, &user, &mint.pubkey(), &spl_token_2022::ID),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user),
+        &[&env.user, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+
+    let user_ata =
+        get_associated_token_address_with_program_id(&user, &mint.pubkey(), &spl_token_2022::ID);
+    mint_to(env, &mint.pubkey(), &user_ata, amount);
+
+    (mint.pubkey(), user_ata)
+}
+
+fn token_program_of(env: &TestEnv, mint: &Pubkey) -> Pubkey {
+    env.svm.get_account(mint).unwrap().owner
+}
+
+fn vault_ata_address(env: &TestEnv, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(&env.vault, mint, &token_program_of(env, mint))
+}
+
+fn mint_to(env: &mut TestEnv, mint: &Pubkey, token_account: &Pubkey, amount: u64) {
+    let mint_instruction = spl_token_2022::instruction::mint_to(
+        &token_program_of(env, mint),
+        mint,
+        token_account,
+        &env.user.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    send(env, &[mint_instruction]).unwrap();
+}
+
+fn token_vault_address(env: &TestEnv, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"token_vault", env.vault_state.as_ref(), mint.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+fn deposit_spl(
+    env: &mut TestEnv,
+    mint: &Pubkey,
+    user_ata: &Pubkey,
+    amount: u64,
+) -> Result<(), TransactionError> {
+    let deposit_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::DepositSpl {
+            user: env.user.pubkey(),
+            mint: *mint,
+            user_ata: *user_ata,
+            vault: env.vault,
+            vault_ata: vault_ata_address(env, mint),
+            token_vault: token_vault_address(env, mint),
+            vault_state: env.vault_state,
+            token_program: token_program_of(env, mint),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositSpl { amount }.data(),
+    };
+    send(env, &[deposit_instruction])
+}
+
+fn withdraw_spl(
+    env: &mut TestEnv,
+    mint: &Pubkey,
+    user_ata: &Pubkey,
+    amount: u64,
+) -> Result<(), TransactionError> {
+    let withdraw_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::WithdrawSpl {
+            user: env.user.pubkey(),
+            mint: *mint,
+            user_ata: *user_ata,
+            vault: env.vault,
+            vault_ata: vault_ata_address(env, mint),
+            token_vault: token_vault_address(env, mint),
+            vault_state: env.vault_state,
+            token_program: token_program_of(env, mint),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawSpl { amount }.data(),
+    };
+    send(env, &[withdraw_instruction])
+}
+
+fn token_balance(env: &TestEnv, token_account: &Pubkey) -> u64 {
+    let account = env.svm.get_account(token_account).unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Transfer fees harvested to a Token-2022 mint.
+fn withheld_fees(env: &TestEnv, mint: &Pubkey) -> u64 {
+    let account = env.svm.get_account(mint).unwrap();
+    let mint_state =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).unwrap();
+    mint_state
+        .get_extension::<TransferFeeConfig>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+fn is_closed(env: &TestEnv, address: &Pubkey) -> bool {
+    env.svm.get_account(address).is_none_or(|a| a.lamports == 0)
+}
+
+/// Creates a one-of-one NFT held by the user. The vault only reads the metadata and master
+/// edition accounts, so they are written directly instead of minted through Token Metadata.
+fn create_nft(env: &mut TestEnv) -> (Pubkey, Pubkey) {
+    let mint = Keypair::new();
+    let user = env.user.pubkey();
+    let space = spl_token::state::Mint::LEN;
+
+    let instructions = [
+        solana_sdk::system_instruction::create_account(
+            &user,
+            &mint.pubkey(),
+            env.svm.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &user, None, 0)
+            .unwrap(),
+        create_associated_token_account(&user, &user, &mint.pubkey(), &spl_token::ID),
+    ];
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user),
+        &[&env.user, &mint],
+        env.svm.latest_blockhash(),
+    );
+    env.svm.send_transaction(tx).unwrap();
+
+    let user_ata = get_associated_token_address(&user, &mint.pubkey());
+    mint_to(env, &mint.pubkey(), &user_ata, 1);
+
+    // Token Metadata is never invoked, it only has to be an executable program
+    let stand_in_bytes = include_bytes!("../target/deploy/mock_strategy.so");
+    env.svm.add_program(mpl_token_metadata::ID, stand_in_bytes).unwrap();
+
+    let metadata = Metadata {
+        key: Key::MetadataV1,
+        update_authority: user,
+        mint: mint.pubkey(),
+        name: "Vault NFT".to_string(),
+        symbol: "VNFT".to_string(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    };
+    let master_edition = MasterEdition {
+        key: Key::MasterEditionV2,
+        supply: 0,
+        max_supply: Some(0),
+    };
+    set_metadata_account(env, Metadata::find_pda(&mint.pubkey()).0, metadata.try_to_vec().unwrap());
+    set_metadata_account(
+        env,
+        MasterEdition::find_pda(&mint.pubkey()).0,
+        master_edition.try_to_vec().unwrap(),
+    );
+
+    (mint.pubkey(), user_ata)
+}
+
+fn set_metadata_account(env: &mut TestEnv, address: Pubkey, data: Vec<u8>) {
+    let account = Account {
+        lamports: env.svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: mpl_token_metadata::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.svm.set_account(address, account).unwrap();
+}
+
+fn deposit_nft(env: &mut TestEnv, mint: &Pubkey, user_ata: &Pubkey) -> Result<(), TransactionError> {
+    let deposit_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::DepositNft {
+            user: env.user.pubkey(),
+            mint: *mint,
+            user_ata: *user_ata,
+            metadata: Metadata::find_pda(mint).0,
+            master_edition: MasterEdition::find_pda(mint).0,
+            vault: env.vault,
+            vault_ata: vault_ata_address(env, mint),
+            token_vault: token_vault_address(env, mint),
+            vault_state: env.vault_state,
+            metadata_program: mpl_token_metadata::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositNft {}.data(),
+    };
+    send(env, &[deposit_instruction])
+}
+
+fn withdraw_nft(env: &mut TestEnv, mint: &Pubkey, user_ata: &Pubkey) -> Result<(), TransactionError> {
+    let withdraw_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::WithdrawNft {
+            user: env.user.pubkey(),
+            mint: *mint,
+            user_ata: *user_ata,
+            vault: env.vault,
+            vault_ata: vault_ata_address(env, mint),
+            token_vault: token_vault_address(env, mint),
+            vault_state: env.vault_state,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawNft {}.data(),
+    };
+    send(env, &[withdraw_instruction])
+}
+
+fn fetch_vault_state(env: &TestEnv) -> VaultState {
+    let account = env.svm.get_account(&env.vault_state).unwrap();
+    VaultState::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn balance(env: &TestEnv, address: &Pubkey) -> u64 {
+    env.svm.get_balance(address).unwrap_or(0)
+}
+
+fn assert_vault_error(result: Result<(), TransactionError>, expected: VaultError) {
+    let err = result.expect_err("transaction should have failed");
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into()))
+    );
+}
+
+#[test]
+fn test_deposit_and_withdraw_track_totals() {
+    let mut env = setup();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    deposit(&mut env, LAMPORTS_PER_SOL).unwrap();
+    withdraw(&mut env, LAMPORTS_PER_SOL / 2).unwrap();
+
+    let vault_state = fetch_vault_state(&env);
+    assert_eq!(vault_state.deposited, DEPOSIT_AMOUNT + LAMPORTS_PER_SOL);
+    assert_eq!(vault_state.withdrawn, LAMPORTS_PER_SOL / 2);
+    assert_eq!(
+        balance(&env, &env.vault),
+        env.rent_exempt + vault_state.deposited - vault_state.withdrawn
+    );
+}
+
+#[test]
+fn test_withdraw_down_to_rent_exemption() {
+    let mut env = setup();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    withdraw(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    assert_eq!(balance(&env, &env.vault), env.rent_exempt);
+    assert_eq!(fetch_vault_state(&env).withdrawn, DEPOSIT_AMOUNT);
+}
+
+#[test]
+fn test_withdraw_below_rent_exemption_fails() {
+    let mut env = setup();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    assert_vault_error(
+        withdraw(&mut env, DEPOSIT_AMOUNT + 1),
+        VaultError::InsufficientFunds,
+    );
+    assert_eq!(balance(&env, &env.vault), env.rent_exempt + DEPOSIT_AMOUNT);
+    assert_eq!(fetch_vault_state(&env).withdrawn, 0);
+}
+
+#[test]
+fn test_withdraw_from_empty_vault_fails() {
+    let mut env = setup();
+
+    assert_vault_error(withdraw(&mut env, 1), VaultError::InsufficientFunds);
+}
+
+#[test]
+fn test_close_returns_the_whole_balance() {
+    let mut env = setup();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    withdraw(&mut env, LAMPORTS_PER_SOL).unwrap();
+
+    let user_before = balance(&env, &env.user.pubkey());
+    let state_rent = balance(&env, &env.vault_state);
+    let vault_balance = balance(&env, &env.vault);
+
+    close(&mut env).unwrap();
+
+    assert_eq!(balance(&env, &env.vault), 0);
+    assert!(env.svm.get_account(&env.vault_state).is_none_or(|a| a.lamports == 0));
+    assert_eq!(
+        balance(&env, &env.user.pubkey()),
+        user_before + vault_balance + state_rent - 5000
+    );
+}
+
+#[test]
+fn test_token_vault_opened_by_someone_else_is_counted_once() {
+    let mut env = setup();
+    let (mint, user_ata) = create_funded_mint(&mut env, 1_000);
+
+    // The vault ATA is created and funded directly, without going through the program
+    let vault_ata = get_associated_token_address(&env.vault, &mint);
+    let create_instruction =
+        create_associated_token_account(&env.user.pubkey(), &env.vault, &mint, &spl_token::ID);
+    send(&mut env, &[create_instruction]).unwrap();
+    mint_to(&mut env, &mint, &vault_ata, 100);
+    assert_eq!(fetch_vault_state(&env).token_vaults, 0);
+
+    deposit_spl(&mut env, &mint, &user_ata, 500).unwrap();
+    deposit_spl(&mut env, &mint, &user_ata, 100).unwrap();
+    assert_eq!(fetch_vault_state(&env).token_vaults, 1);
+
+    // Closing is refused until the token vault is swept
+    assert_vault_error(close(&mut env), VaultError::TokenVaultsOpen);
+
+    // Emptying the vault closes it and its record without underflowing the count
+    withdraw_spl(&mut env, &mint, &user_ata, 700).unwrap();
+    assert_eq!(token_balance(&env, &user_ata), 1_100);
+    assert!(is_closed(&env, &vault_ata));
+    assert_eq!(fetch_vault_state(&env).token_vaults, 0);
+
+    close(&mut env).unwrap();
+}
+
+#[test]
+fn test_withdraw_all_of_a_transfer_fee_token() {
+    let mut env = setup();
+    let (mint, user_ata) = create_funded_fee_mint(&mut env, 1_000, 100);
+
+    // 1% of the deposit is withheld in the vault's token account
+    deposit_spl(&mut env, &mint, &user_ata, 500).unwrap();
+    let vault_ata = vault_ata_address(&env, &mint);
+    assert_eq!(token_balance(&env, &vault_ata), 495);
+
+    // The withheld fee is harvested to the mint so the emptied account can close
+    withdraw_spl(&mut env, &mint, &user_ata, 495).unwrap();
+    assert_eq!(token_balance(&env, &user_ata), 500 + 490);
+    assert_eq!(withheld_fees(&env, &mint), 5);
+    assert!(is_closed(&env, &vault_ata));
+    assert!(is_closed(&env, &token_vault_address(&env, &mint)));
+    assert_eq!(fetch_vault_state(&env).token_vaults, 0);
+}
+
+#[test]
+fn test_close_sweeps_spl_token_and_token_2022_vaults() {
+    let mut env = setup();
+    let (mint, user_ata) = create_funded_mint(&mut env, 1_000);
+    let (fee_mint, fee_user_ata) = create_funded_fee_mint(&mut env, 1_000, 100);
+
+    deposit_spl(&mut env, &mint, &user_ata, 500).unwrap();
+    deposit_spl(&mut env, &fee_mint, &fee_user_ata, 500).unwrap();
+    assert_eq!(fetch_vault_state(&env).token_vaults, 2);
+
+    close_with_token_vaults(&mut env, &[(mint, user_ata), (fee_mint, fee_user_ata)]).unwrap();
+
+    assert_eq!(token_balance(&env, &user_ata), 1_000);
+    assert_eq!(token_balance(&env, &fee_user_ata), 500 + 490);
+    assert_eq!(withheld_fees(&env, &fee_mint), 5);
+    for mint in [mint, fee_mint] {
+        assert!(is_closed(&env, &vault_ata_address(&env, &mint)));
+        assert!(is_closed(&env, &token_vault_address(&env, &mint)));
+    }
+    assert!(is_closed(&env, &env.vault_state));
+}
+
+#[test]
+fn test_deposit_and_withdraw_nft() {
+    let mut env = setup();
+    let (mint, user_ata) = create_nft(&mut env);
+
+    deposit_nft(&mut env, &mint, &user_ata).unwrap();
+    let vault_ata = vault_ata_address(&env, &mint);
+    assert_eq!(token_balance(&env, &vault_ata), 1);
+    assert_eq!(token_balance(&env, &user_ata), 0);
+    assert_eq!(fetch_vault_state(&env).token_vaults, 1);
+
+    withdraw_nft(&mut env, &mint, &user_ata).unwrap();
+    assert_eq!(token_balance(&env, &user_ata), 1);
+    assert!(is_closed(&env, &vault_ata));
+    assert!(is_closed(&env, &token_vault_address(&env, &mint)));
+    assert_eq!(fetch_vault_state(&env).token_vaults, 0);
+}
+
+#[test]
+fn test_spend_within_allowance() {
+    let mut env = setup();
+    let spender = Keypair::new();
+    env.svm.airdrop(&spender.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    approve_spender(&mut env, &spender.pubkey());
+
+    let spender_before = balance(&env, &spender.pubkey());
+    spend(&mut env, &spender, ALLOWANCE_AMOUNT / 2).unwrap();
+    spend(&mut env, &spender, ALLOWANCE_AMOUNT / 2).unwrap();
+
+    assert_eq!(
+        balance(&env, &spender.pubkey()),
+        spender_before + ALLOWANCE_AMOUNT - 2 * 5000
+    );
+    assert_eq!(fetch_vault_state(&env).withdrawn, ALLOWANCE_AMOUNT);
+
+    assert_vault_error(spend(&mut env, &spender, 1), VaultError::AllowanceExceeded);
+}
+
+#[test]
+fn test_allowance_resets_each_period() {
+    let mut env = setup();
+    let spender = Keypair::new();
+    env.svm.airdrop(&spender.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    approve_spender(&mut env, &spender.pubkey());
+    spend(&mut env, &spender, ALLOWANCE_AMOUNT).unwrap();
+
+    // Skip two and a half periods: the allowance resets once, aligned to the approval
+    warp(&mut env, ALLOWANCE_PERIOD * 5 / 2);
+    spend(&mut env, &spender, ALLOWANCE_AMOUNT).unwrap();
+
+    let account = env
+        .svm
+        .get_account(&allowance_address(&env, &spender.pubkey()))
+        .unwrap();
+    let allowance = Allowance::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(allowance.spent, ALLOWANCE_AMOUNT);
+    assert_eq!(
+        env.svm.get_sysvar::<Clock>().unix_timestamp - allowance.period_start,
+        ALLOWANCE_PERIOD / 2
+    );
+}
+
+#[test]
+fn test_allowance_with_unbounded_period_never_resets() {
+    let mut allowance = Allowance {
+        vault_state: Pubkey::new_unique(),
+        spender: Pubkey::new_unique(),
+        amount_per_period: ALLOWANCE_AMOUNT,
+        period: i64::MAX,
+        period_start: 1_700_000_000,
+        spent: 0,
+        bump: 255,
+    };
+
+    allowance.record_spend(ALLOWANCE_AMOUNT, 1_700_000_000).unwrap();
+    assert!(allowance.record_spend(1, i64::MAX).is_err());
+    assert_eq!(allowance.period_start, 1_700_000_000);
+    assert_eq!(allowance.spent, ALLOWANCE_AMOUNT);
+}
+
+#[test]
+fn test_spend_without_allowance_fails() {
+    let mut env = setup();
+    let spender = Keypair::new();
+    env.svm.airdrop(&spender.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    let err = spend(&mut env, &spender, 1).expect_err("transaction should have failed");
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(anchor_lang::error::ErrorCode::AccountNotInitialized.into())
+        )
+    );
+}
+
+#[test]
+fn test_spend_keeps_the_vault_rent_exempt() {
+    let mut env = setup();
+    let spender = Keypair::new();
+    env.svm.airdrop(&spender.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    deposit(&mut env, ALLOWANCE_AMOUNT / 2).unwrap();
+    approve_spender(&mut env, &spender.pubkey());
+
+    assert_vault_error(
+        spend(&mut env, &spender, ALLOWANCE_AMOUNT),
+        VaultError::InsufficientFunds,
+    );
+}
+
+#[test]
+fn test_strategy_round_trip_tracks_principal_and_yield() {
+    let mut env = setup();
+    whitelist_mock_strategy(&mut env);
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    invest(&mut env, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL).unwrap();
+
+    let vault_state = fetch_vault_state(&env);
+    assert_eq!(vault_state.invested, LAMPORTS_PER_SOL);
+    assert_eq!(vault_state.strategy_program, Some(mock_strategy::ID));
+    assert_eq!(balance(&env, &pool_address(&env)), LAMPORTS_PER_SOL);
+    assert_eq!(balance(&env, &strategy_signer_address(&env)), 0);
+
+    // Funds in the strategy can't be withdrawn from the vault
+    assert_vault_error(
+        withdraw(&mut env, LAMPORTS_PER_SOL + 1),
+        VaultError::InsufficientFunds,
+    );
+    assert_vault_error(close(&mut env), VaultError::StrategyFundsOpen);
+
+    // The strategy earns some yield
+    let pool = pool_address(&env);
+    env.svm.airdrop(&pool, LAMPORTS_PER_SOL / 10).unwrap();
+
+    divest(&mut env).unwrap();
+
+    let vault_state = fetch_vault_state(&env);
+    assert_eq!(vault_state.invested, 0);
+    assert_eq!(vault_state.yield_earned, LAMPORTS_PER_SOL / 10);
+    assert_eq!(vault_state.strategy_program, None);
+    assert_eq!(
+        balance(&env, &env.vault),
+        env.rent_exempt + DEPOSIT_AMOUNT + LAMPORTS_PER_SOL / 10
+    );
+    assert_eq!(balance(&env, &strategy_signer_address(&env)), 0);
+}
+
+#[test]
+fn test_strategy_takes_only_what_it_uses() {
+    let mut env = setup();
+    whitelist_mock_strategy(&mut env);
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+    invest(&mut env, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL / 2).unwrap();
+
+    // The unused half goes straight back to the vault
+    assert_eq!(fetch_vault_state(&env).invested, LAMPORTS_PER_SOL / 2);
+    assert_eq!(balance(&env, &pool_address(&env)), LAMPORTS_PER_SOL / 2);
+    assert_eq!(balance(&env, &strategy_signer_address(&env)), 0);
+    assert_eq!(
+        balance(&env, &env.vault),
+        env.rent_exempt + DEPOSIT_AMOUNT - LAMPORTS_PER_SOL / 2
+    );
+}
+
+#[test]
+fn test_strategy_cannot_sign_as_the_vault() {
+    let mut env = setup();
+    whitelist_mock_strategy(&mut env);
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    // Naming the vault as the depositor doesn't make it a signer of the strategy call
+    let data = instruction::Invest {
+        amount: LAMPORTS_PER_SOL,
+        data: mock_strategy::instruction::Deposit {
+            amount: LAMPORTS_PER_SOL,
+        }
+        .data(),
+    }
+    .data();
+    let mut invest_instruction = strategy_instruction(&env, data);
+    let vault_pool =
+        Pubkey::find_program_address(&[b"pool", env.vault.as_ref()], &mock_strategy::ID).0;
+    let strategy_accounts = invest_instruction.accounts.len() - 3;
+    invest_instruction.accounts[strategy_accounts] = AccountMeta::new(env.vault, false);
+    invest_instruction.accounts[strategy_accounts + 1] = AccountMeta::new(vault_pool, false);
+
+    assert!(send(&mut env, &[invest_instruction]).is_err());
+    assert_eq!(balance(&env, &env.vault), env.rent_exempt + DEPOSIT_AMOUNT);
+    assert_eq!(balance(&env, &vault_pool), 0);
+    assert_eq!(fetch_vault_state(&env).invested, 0);
+}
+
+#[test]
+fn test_strategy_cannot_take_more_than_invested() {
+    let mut env = setup();
+    whitelist_mock_strategy(&mut env);
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    // The strategy signer only ever holds the amount being invested
+    assert!(invest(&mut env, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL + 1).is_err());
+    assert_eq!(balance(&env, &pool_address(&env)), 0);
+    assert_eq!(fetch_vault_state(&env).invested, 0);
+    assert_vault_error(
+        invest(&mut env, DEPOSIT_AMOUNT + 1, DEPOSIT_AMOUNT + 1),
+        VaultError::InsufficientFunds,
+    );
+}
+
+#[test]
+fn test_strategy_must_be_whitelisted() {
+    let mut env = setup();
+
+    let init_instruction = init_strategy_registry_instruction(&env.user.pubkey());
+    send(&mut env, &[init_instruction]).unwrap();
+
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    assert_vault_error(
+        invest(&mut env, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL),
+        VaultError::StrategyNotWhitelisted,
+    );
+}
+
+#[test]
+fn test_only_upgrade_authority_can_init_strategy_registry() {
+    let mut env = setup();
+    let intruder = Keypair::new();
+    env.svm.airdrop(&intruder.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    let init_instruction = init_strategy_registry_instruction(&intruder.pubkey());
+    let tx = Transaction::new_signed_with_payer(
+        &[init_instruction],
+        Some(&intruder.pubkey()),
+        &[&intruder],
+        env.svm.latest_blockhash(),
+    );
+    let result = env.svm.send_transaction(tx).map(|_| ()).map_err(|e| e.err);
+
+    assert_vault_error(result, VaultError::Unauthorized);
+    assert!(env.svm.get_account(&registry_address()).is_none());
+}
+
+#[test]
+fn test_vault_with_beneficiary_cannot_invest() {
+    let mut env = setup();
+    whitelist_mock_strategy(&mut env);
+    deposit(&mut env, DEPOSIT_AMOUNT).unwrap();
+
+    let lock_instruction = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Lock {
+            user: env.user.pubkey(),
+            vault_state: env.vault_state,
+        }
+        .to_account_metas(None),
+        data: instruction::Lock {
+            mode: LockMode::TimeLock { unlock_ts: 0 },
+            beneficiary: Some(Pubkey::new_unique()),
+        }
+        .data(),
+    };
+    send(&mut env, &[lock_instruction]).unwrap();
+
+    assert_vault_error(
+        invest(&mut env, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL),
+        VaultError::InvalidBeneficiary,
+    );
+}